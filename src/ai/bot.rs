@@ -0,0 +1,240 @@
+//! Plays a seat through an external program, so bots written in any language can play.
+//!
+//! The engine and the bot talk in lines of text over the bot's stdin and stdout. Cards are
+//! written as `S:2` and moves as `trail C:5`, `take C:5 0 2` or `build 9 C:5 1`, where the
+//! numbers after the card are indices of piles on the table.
+//!
+//! ```text
+//! engine: kasino 2                      once, when the bot is started
+//! bot:    ready
+//! engine: state                         every turn of the seat
+//!         seat <seat>
+//!         round <round> <last round: 0|1>
+//!         deck <cards left>
+//!         turn <seat>
+//!         capture <seat that captured last or ->
+//!         hand <card> ...
+//!         variant <standard|no-sweeps>
+//!         target <points that win the match, 0 for a single deal>
+//!         deal <points> ...             one line per deal of the match so far, a number per seat
+//!         player <seat> <cards in hand> <sweeps> <name>   one line per player,
+//!         captured <seat> <card> ...                        each followed by its captured cards
+//!         pile <index> <value> <build owner or -> <card> ...   one line per pile
+//!         move <index> <move>           one line per legal move
+//!         go <ask>                      numbered from 1 on
+//! bot:    play <ask> <index> | play <ask> <move>
+//! engine: error <reason>                when the reply was late or illegal
+//! engine: quit                          when the seat is no longer played by the bot, such as when
+//!                                       the game it plays in is closed
+//! ```
+//!
+//! Lines from the bot that do not start with the expected word are ignored, as are plays that do
+//! not repeat the number of the last `go`, which were meant for an earlier turn. A bot that does
+//! not reply in time, or replies with an illegal move, has the first legal move played for it, and
+//! after three such strikes it is no longer asked.
+
+use crate::types::{Game, Intent};
+use crate::ai::Controller;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const PROTOCOL_VERSION: u8 = 2;
+const MAX_STRIKES: u8 = 3;
+
+#[derive(Debug)]
+pub struct Bot {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    timeout: Duration,
+    strikes: u8,
+    /// How many times the bot has been asked to play.
+    asked: u32,
+}
+
+impl Bot {
+    /// Starts `command`, split on whitespace into program and arguments, and waits for it to
+    /// be ready. `timeout` applies to every reply.
+    pub fn spawn(command: &str, timeout: Duration) -> io::Result<Bot> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty bot command"))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() {
+                        break;
+                    },
+                    Err(_) => break
+                }
+            }
+        });
+
+        let mut bot = Bot {
+            child,
+            stdin,
+            lines,
+            timeout,
+            strikes: 0,
+            asked: 0,
+        };
+        bot.send(&format!("kasino {}", PROTOCOL_VERSION))?;
+        bot.receive("ready").map_err(|e| io::Error::new(io::ErrorKind::TimedOut, e))?;
+        Ok(bot)
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()
+    }
+
+    /// Waits for a line starting with `word` and returns the rest of it.
+    fn receive(&mut self, word: &str) -> Result<String, String> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(left) {
+                Ok(line) => {
+                    let mut parts = line.trim().splitn(2, ' ');
+                    if parts.next() == Some(word) {
+                        return Ok(parts.next().unwrap_or("").trim().to_string());
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) => return Err(format!("no '{}' within {:?}", word, self.timeout)),
+                Err(RecvTimeoutError::Disconnected) => return Err("the bot has exited".to_string()),
+            }
+        }
+    }
+
+    fn ask(&mut self, game: &Game, seat: u8, intents: &[Intent]) -> Result<Intent, String> {
+        // replies that came too late for an earlier turn are not taken for this one
+        while self.lines.try_recv().is_ok() {}
+        self.asked += 1;
+        for line in state(game, seat, intents, self.asked) {
+            self.send(&line).map_err(|e| e.to_string())?;
+        }
+        let ask = self.asked.to_string();
+        let reply = loop {
+            let reply = self.receive("play")?;
+            let mut parts = reply.splitn(2, ' ');
+            if parts.next() == Some(ask.as_str()) {
+                break parts.next().unwrap_or("").trim().to_string();
+            }
+        };
+        let intent = match reply.parse::<usize>() {
+            Ok(index) => intents.get(index).cloned().ok_or(format!("no move {}", index))?,
+            Err(_) => reply.parse::<Intent>()?,
+        };
+        game.validate(&intent).map_err(|e| format!("illegal move '{}': {:?}", reply, e))?;
+        Ok(intent)
+    }
+}
+
+impl Controller for Bot {
+    fn choose(&mut self, game: &Game) -> Option<Intent> {
+        let seat = game.player_turn?;
+        let intents = game.legal_intents();
+        let fallback = intents.first().cloned();
+        if self.strikes >= MAX_STRIKES {
+            return fallback;
+        }
+        match self.ask(game, seat, &intents) {
+            Ok(intent) => Some(intent),
+            Err(reason) => {
+                self.strikes += 1;
+                eprintln!("bot in seat {}: {}", seat, reason);
+                let _ = self.send(&format!("error {}", reason));
+                fallback
+            }
+        }
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + Duration::from_millis(200);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// What `seat` may see of the game, and its legal moves, as protocol lines of the `ask`th turn.
+pub fn state(game: &Game, seat: u8, intents: &[Intent], ask: u32) -> Vec<String> {
+    let mut lines = vec!("state".to_string());
+    lines.append(&mut game.view(seat).to_lines());
+    for (i, intent) in intents.iter().enumerate() {
+        lines.push(format!("move {} {}", i, intent));
+    }
+    lines.push(format!("go {}", ask));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Player;
+    use std::fs;
+
+    fn bot(name: &str, on_go: &str, timeout: Duration) -> Bot {
+        let script = std::env::temp_dir().join(format!("rcards-bot-{}-{}.sh", name, std::process::id()));
+        fs::write(&script, format!(
+            "while read line; do case \"$line\" in kasino*) echo ready ;; go*) ask=${{line#go }}; {} ;; quit) exit 0 ;; esac; done\n",
+            on_go
+        )).unwrap();
+        Bot::spawn(&format!("sh {}", script.display()), timeout).unwrap()
+    }
+
+    fn game() -> Game {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.start();
+        game
+    }
+
+    #[test]
+    fn test_bot_plays_by_index() {
+        let game = game();
+        let mut bot = bot("index", "echo 'info thinking'; echo \"play $ask 1\"", Duration::from_secs(5));
+        assert_eq!(bot.choose(&game), Some(game.legal_intents()[1].clone()));
+        assert_eq!(bot.strikes, 0);
+    }
+
+    #[test]
+    fn test_illegal_and_late_replies_fall_back() {
+        let game = game();
+        let first = game.legal_intents()[0].clone();
+        let mut illegal = bot("illegal", "echo \"play $ask trail H:14\"", Duration::from_secs(5));
+        assert_eq!(illegal.choose(&game), Some(first.clone()));
+        assert_eq!(illegal.strikes, 1);
+
+        let mut late = bot("late", "sleep 1", Duration::from_millis(100));
+        assert_eq!(late.choose(&game), Some(first));
+        assert_eq!(late.strikes, 1);
+
+        // a play for an earlier turn is passed over
+        let mut stale = bot("stale", "echo 'play 0 1'; echo \"play $ask 0\"", Duration::from_secs(5));
+        assert_eq!(stale.choose(&game), Some(game.legal_intents()[0].clone()));
+        assert_eq!(stale.strikes, 0);
+    }
+}
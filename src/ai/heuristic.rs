@@ -0,0 +1,113 @@
+use crate::types::{Game, Card, Suit, Intent};
+use crate::kasino::partitions;
+use crate::ai::{Controller, Difficulty};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Picks the legal intent that looks best right now, by what it captures and what it leaves
+/// on the table. Easy opponents play at random half the time, and only hard opponents look out
+/// for leaving a table the next player can sweep.
+#[derive(Debug)]
+pub struct Heuristic {
+    difficulty: Difficulty,
+}
+
+impl Heuristic {
+    pub fn new(difficulty: Difficulty) -> Heuristic {
+        Heuristic {
+            difficulty
+        }
+    }
+
+    pub fn evaluate(&self, game: &Game, intent: &Intent) -> f64 {
+        let mut remaining: Vec<u8> = (0..game.table.len()).filter_map(|p| game.pile_value(p)).collect();
+        let captured = |piles: &Vec<usize>| -> f64 {
+            piles.iter()
+                .flat_map(|p| game.table.get(*p).unwrap().cards())
+                .map(|c| card_worth(&c))
+                .sum()
+        };
+        let without = |remaining: &Vec<u8>, piles: &Vec<usize>| -> Vec<u8> {
+            remaining.iter().enumerate().filter(|(i, _)| !piles.contains(i)).map(|(_, v)| *v).collect()
+        };
+
+        let worth = match intent {
+            Intent::Trail(card) => {
+                remaining.push(card.table_value());
+                -1.5 * card_worth(card)
+            },
+            Intent::Take(card, piles) => {
+                remaining = without(&remaining, piles);
+                let sweep = if remaining.is_empty() { 1.0 } else { 0.0 };
+                card_worth(card) + captured(piles) + sweep + 0.05 * piles.len() as f64
+            },
+            Intent::Build(card, piles, value) => {
+                remaining = without(&remaining, piles);
+                remaining.push(*value);
+                0.2 + 0.5 * (card_worth(card) + captured(piles))
+            },
+        };
+
+        if self.difficulty == Difficulty::Hard && sweepable(&remaining) {
+            worth - 0.8
+        } else {
+            worth
+        }
+    }
+}
+
+impl Controller for Heuristic {
+    fn choose(&mut self, game: &Game) -> Option<Intent> {
+        let intents = game.legal_intents();
+        let mut rng = rand::thread_rng();
+        if self.difficulty == Difficulty::Easy && rng.gen_bool(0.5) {
+            return intents.choose(&mut rng).cloned();
+        }
+        intents.into_iter()
+            .map(|intent| (self.evaluate(game, &intent), intent))
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+            .map(|(_, intent)| intent)
+    }
+}
+
+/// How much a card counts towards the points at the end of the game: a little for most cards
+/// and most spades, and a point or two for aces and the casinos.
+pub fn card_worth(card: &Card) -> f64 {
+    let mut worth = 0.1;
+    if card.suit == Suit::SPADES {
+        worth += 0.15;
+    }
+    match card.hand_value() {
+        14 | 15 => worth + 1.0,
+        16 => worth + 2.0,
+        _ => worth
+    }
+}
+
+/// Whether a single card could take every pile of a table with the given pile values.
+fn sweepable(values: &[u8]) -> bool {
+    !values.is_empty() && values.len() <= 6 && (1..=16).any(|v| partitions(values, v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Deck, Player};
+
+    #[test]
+    fn test_prefers_big_casino() {
+        let mut player = Player::new(0, "player1");
+        player.deal(&mut vec!(Card::new(Suit::HEARTS, 10, Deck::empty()), Card::new(Suit::CLUBS, 3, Deck::empty())));
+        let mut game = Game {
+            players: vec!(player, Player::new(1, "player2")),
+            player_turn: Some(0),
+            ..Default::default()
+        };
+        game.table.new_pile(vec!(Card::new(Suit::DIAMONDS, 10, Deck::empty())));
+        game.table.new_pile(vec!(Card::new(Suit::CLUBS, 3, Deck::empty())));
+        game.table.new_pile(vec!(Card::new(Suit::HEARTS, 6, Deck::empty())));
+
+        let intent = Heuristic::new(Difficulty::Hard).choose(&game).unwrap();
+        assert_eq!(intent, Intent::Take(Card::new(Suit::HEARTS, 10, Deck::empty()), vec!(0)));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::types::{Game, Intent};
+use crate::ai::Controller;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+/// Searches for the best intent with Monte Carlo tree search. The cards the seat in turn cannot
+/// see, the other hands and the deck, are dealt at random into a number of worlds consistent with
+/// what it has seen; each world gets a search tree of its own, and the intent visited most across
+/// all worlds is played.
+#[derive(Debug)]
+pub struct Mcts {
+    pub iterations: usize,
+    pub time: Option<Duration>,
+    pub worlds: usize,
+    pub exploration: f64,
+}
+
+impl Default for Mcts {
+    fn default() -> Self {
+        Mcts {
+            iterations: 2000,
+            time: Some(Duration::from_secs(2)),
+            worlds: 8,
+            exploration: 0.7,
+        }
+    }
+}
+
+struct Node {
+    intent: Option<Intent>,
+    mover: Option<u8>,
+    visits: f64,
+    reward: f64,
+    children: Vec<Node>,
+    untried: Option<Vec<Intent>>,
+}
+
+impl Node {
+    fn new(intent: Option<Intent>, mover: Option<u8>) -> Node {
+        Node {
+            intent,
+            mover,
+            visits: 0.0,
+            reward: 0.0,
+            children: vec!(),
+            untried: None,
+        }
+    }
+
+    fn update(&mut self, rewards: &[f64]) {
+        self.visits += 1.0;
+        if let Some(mover) = self.mover {
+            self.reward += rewards[mover as usize];
+        }
+    }
+}
+
+impl Mcts {
+    pub fn new(iterations: usize, time: Option<Duration>) -> Mcts {
+        Mcts {
+            iterations,
+            time,
+            ..Default::default()
+        }
+    }
+
+    /// Runs one iteration in `game`: selects down the tree, expands one intent, plays the rest of
+    /// the game at random and credits every node's mover with its reward.
+    fn iterate<R: Rng>(&self, node: &mut Node, game: &mut Game, rng: &mut R) -> Vec<f64> {
+        if node.untried.is_none() {
+            let mut intents = game.legal_intents();
+            intents.shuffle(rng);
+            node.untried = Some(intents);
+        }
+
+        let rewards = if let Some(intent) = node.untried.as_mut().unwrap().pop() {
+            let mover = game.player_turn;
+            let _ = game.play(intent.clone());
+            let mut child = Node::new(Some(intent), mover);
+            let rewards = rollout(game, rng);
+            child.update(&rewards);
+            node.children.push(child);
+            rewards
+        } else if node.children.is_empty() {
+            rewards(game)
+        } else {
+            let log_visits = node.visits.ln();
+            let child = node.children.iter_mut().max_by(|a, b| {
+                self.ucb(a, log_visits).partial_cmp(&self.ucb(b, log_visits)).unwrap()
+            }).unwrap();
+            let _ = game.play(child.intent.clone().unwrap());
+            self.iterate(child, game, rng)
+        };
+
+        node.update(&rewards);
+        rewards
+    }
+
+    fn ucb(&self, node: &Node, log_visits: f64) -> f64 {
+        node.reward / node.visits + self.exploration * (log_visits / node.visits).sqrt()
+    }
+}
+
+impl Controller for Mcts {
+    fn choose(&mut self, game: &Game) -> Option<Intent> {
+        let seat = game.player_turn?;
+        let intents = game.legal_intents();
+        if intents.len() <= 1 {
+            return intents.into_iter().next();
+        }
+
+        let mut rng = rand::thread_rng();
+        let worlds: Vec<Game> = (0..self.worlds.max(1)).map(|_| determinize(game, seat, &mut rng)).collect();
+        let mut trees: Vec<Node> = worlds.iter().map(|_| Node::new(None, None)).collect();
+        let started = Instant::now();
+        for i in 0..self.iterations {
+            if self.time.map(|t| started.elapsed() > t).unwrap_or(false) {
+                break;
+            }
+            let world = i % worlds.len();
+            let mut game = worlds[world].fork();
+            self.iterate(&mut trees[world], &mut game, &mut rng);
+            game.discard();
+        }
+        for world in worlds {
+            world.discard();
+        }
+
+        intents.into_iter().max_by_key(|intent| {
+            trees.iter()
+                .flat_map(|tree| tree.children.iter())
+                .filter(|child| child.intent.as_ref() == Some(intent))
+                .map(|child| child.visits as u64)
+                .sum::<u64>()
+        })
+    }
+}
+
+/// A copy of `game` where the cards `seat` cannot see, the other players' hands and the deck,
+/// are shuffled and dealt again.
+pub fn determinize<R: Rng>(game: &Game, seat: u8, rng: &mut R) -> Game {
+    let mut world = game.fork();
+    let mut unknown = world.deck.cards();
+    for (i, p) in world.players.iter().enumerate() {
+        if i != seat as usize {
+            unknown.append(&mut p.hand.cards());
+        }
+    }
+    unknown.shuffle(rng);
+    for (i, p) in world.players.iter_mut().enumerate() {
+        if i != seat as usize {
+            let count = p.hand.len();
+            p.hand.set_cards(unknown.drain(..count).collect());
+        }
+    }
+    world.deck.set_cards(unknown);
+    world
+}
+
+/// Plays random legal intents until the game is over.
+fn rollout<R: Rng>(game: &mut Game, rng: &mut R) -> Vec<f64> {
+    while game.player_turn.is_some() {
+        match game.legal_intents().choose(rng) {
+            Some(intent) => {
+                let _ = game.play(intent.clone());
+            },
+            None => break
+        }
+    }
+    rewards(game)
+}
+
+/// The reward of every seat for a finished game: mostly whether it won, and a little by how much.
+fn rewards(game: &Game) -> Vec<f64> {
+    let totals: Vec<f64> = game.points().iter().map(|p| p.total() as f64).collect();
+    (0..totals.len()).map(|seat| {
+        let best_other = totals.iter().enumerate()
+            .filter(|(i, _)| *i != seat)
+            .map(|(_, t)| *t)
+            .fold(0.0, f64::max);
+        let diff = totals[seat] - best_other;
+        let result = if diff > 0.0 { 1.0 } else if diff < 0.0 { 0.0 } else { 0.5 };
+        0.8 * result + 0.2 * (0.5 + diff / 22.0).clamp(0.0, 1.0)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Player;
+
+    #[test]
+    fn test_determinize_keeps_what_is_seen() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.start();
+        let world = determinize(&game, 0, &mut rand::thread_rng());
+        assert_eq!(world.players[0].hand.cards(), game.players[0].hand.cards());
+        assert_eq!(world.players[1].hand.len(), 4);
+        assert_eq!(world.deck.len(), game.deck.len());
+        for (pile, original) in world.table.piles().iter().zip(game.table.piles()) {
+            assert_eq!(pile.cards(), original.cards());
+        }
+        world.discard();
+    }
+
+    #[test]
+    fn test_plays_a_legal_intent() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.start();
+        let intent = Mcts::new(50, None).choose(&game).unwrap();
+        assert!(game.validate(&intent).is_ok());
+    }
+}
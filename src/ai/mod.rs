@@ -0,0 +1,190 @@
+use crate::types::{Game, Intent};
+use crate::events::Event;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+pub mod bot;
+pub mod heuristic;
+pub mod mcts;
+pub mod solver;
+
+pub use self::heuristic::Heuristic;
+pub use self::mcts::Mcts;
+pub use self::solver::Expert;
+pub use self::bot::Bot;
+
+/// Plays a seat on behalf of a player who is not at the window.
+pub trait Controller: fmt::Debug + Send {
+    /// Picks an intent for the seat in turn. `None` leaves the turn undecided for now.
+    fn choose(&mut self, game: &Game) -> Option<Intent>;
+
+    /// Whether the controller only holds the seat while the one that plays it thinks elsewhere.
+    fn away(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!("unknown difficulty '{}'", s)),
+        }
+    }
+}
+
+/// Creates the controller described by `spec`: a difficulty (`easy`, `medium`, `hard`) for the
+/// heuristic opponent, `mcts` with an optional iteration budget, as in `mcts:5000`, `expert`, or
+/// `bot:<command>` for an external program speaking the bot protocol.
+pub fn from_spec(spec: &str) -> Result<Box<dyn Controller>, String> {
+    let mut parts = spec.splitn(2, ':');
+    match (parts.next().unwrap(), parts.next()) {
+        ("expert", None) => Ok(Box::new(Expert::default())),
+        ("bot", Some(command)) => {
+            let bot = Bot::spawn(command, Duration::from_secs(5)).map_err(|e| format!("could not start bot '{}': {}", command, e))?;
+            Ok(Box::new(bot))
+        },
+        ("mcts", None) => Ok(Box::new(Mcts::default())),
+        ("mcts", Some(iterations)) => {
+            let iterations = iterations.parse().map_err(|_| format!("invalid iterations '{}'", iterations))?;
+            Ok(Box::new(Mcts::new(iterations, None)))
+        },
+        (difficulty, None) => Ok(Box::new(Heuristic::new(difficulty.parse()?))),
+        _ => Err(format!("unknown controller '{}'", spec)),
+    }
+}
+
+/// A play being chosen by the controller of a seat on a thread of its own, so the window goes on
+/// while the computer thinks.
+pub struct Thinking {
+    seat: u8,
+    chosen: Receiver<(Box<dyn Controller>, Option<Intent>)>,
+}
+
+/// Holds the seat of a controller that is thinking on another thread, so the seat is still
+/// played by the computer. It leaves the turn undecided.
+#[derive(Debug)]
+struct Away;
+
+impl Controller for Away {
+    fn choose(&mut self, _: &Game) -> Option<Intent> {
+        None
+    }
+
+    fn away(&self) -> bool {
+        true
+    }
+}
+
+impl Game {
+    /// Lets the controller of the seat in turn play. The controller is shown a game rebuilt from
+    /// the seat's view, so it cannot peek at other hands or the deck. A controller that comes up
+    /// with an illegal intent plays the first legal one instead. Returns whether a play was made.
+    pub fn advance(&mut self) -> bool {
+        let seat = match self.player_turn {
+            Some(seat) => seat,
+            None => return false
+        };
+        let mut controller = match self.players[seat as usize].controller.take() {
+            Some(controller) => controller,
+            None => return false
+        };
+        let seen = self.view(seat).to_game();
+        let intent = controller.choose(&seen);
+        seen.discard();
+        self.players[seat as usize].controller = Some(controller);
+        self.make(seat, intent)
+    }
+
+    /// Lets the controller of the seat in turn choose a play as `advance` does, but on a thread
+    /// of its own. The play is made by `conclude` once it has been chosen.
+    pub fn think(&mut self) -> Option<Thinking> {
+        let seat = self.player_turn?;
+        let player = &mut self.players[seat as usize];
+        if player.controller.as_ref().is_none_or(|c| c.away()) {
+            return None;
+        }
+        let mut controller = player.controller.replace(Box::new(Away))?;
+        let view = self.view(seat);
+        let (sender, chosen) = mpsc::channel();
+        thread::spawn(move || {
+            let seen = view.to_game();
+            let intent = controller.choose(&seen);
+            seen.discard();
+            let _ = sender.send((controller, intent));
+        });
+        Some(Thinking { seat, chosen })
+    }
+
+    /// Gives the controller of `thinking` its seat back and makes the play it chose, once it has.
+    /// Returns `None` while it is still thinking, and otherwise whether a play was made.
+    pub fn conclude(&mut self, thinking: &Thinking) -> Option<bool> {
+        let (controller, intent) = thinking.chosen.try_recv().ok()?;
+        self.players[thinking.seat as usize].controller = Some(controller);
+        Some(self.make(thinking.seat, intent))
+    }
+
+    /// Plays `intent` for `seat`, or the first legal play if it is not allowed.
+    fn make(&mut self, seat: u8, intent: Option<Intent>) -> bool {
+        match intent {
+            Some(intent) => match self.play(intent) {
+                Ok(()) => true,
+                Err(_) => {
+                    self.emit(Event::IllegalPlay(seat));
+                    match self.legal_intents().into_iter().next() {
+                        Some(intent) => self.play(intent).is_ok(),
+                        None => false
+                    }
+                }
+            },
+            None => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Player;
+
+    #[test]
+    fn test_controller_thinks_on_its_own_thread() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.players[0].controller = Some(from_spec("easy").unwrap());
+        game.start();
+
+        let thinking = game.think().unwrap();
+        // the seat is still the computer's while it thinks, and is not asked again
+        assert!(game.players[0].controller.is_some());
+        assert!(game.think().is_none());
+        assert!(!game.advance());
+        let played = loop {
+            if let Some(played) = game.conclude(&thinking) {
+                break played;
+            }
+            thread::yield_now();
+        };
+        assert!(played);
+        assert_eq!(game.player_turn, Some(1));
+        assert_eq!(game.players[0].hand.len(), 3);
+        assert!(game.players[0].controller.is_some());
+        game.discard();
+    }
+}
@@ -0,0 +1,341 @@
+use crate::types::{Game, Card, Suit, Intent};
+use crate::ai::{Controller, Mcts};
+use crate::replay::Replay;
+use std::collections::HashMap;
+
+/// Solves the last round exactly. Once the deck is empty, a player who has counted the cards
+/// knows every card left, so the rest of the game is a game of perfect information. The solver
+/// searches it with alpha-beta, assuming the other players all play against the seat it solves
+/// for, and remembers positions it has seen by how the cards are distributed.
+#[derive(Default)]
+pub struct Solver {
+    table: HashMap<Vec<u8>, Entry>,
+}
+
+/// The outcome of a solved position: the points the seat ends up ahead of the best other player,
+/// and the plays that get there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub difference: i32,
+    pub line: Vec<Intent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    value: i32,
+    bound: Bound,
+    best: Option<Intent>,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Default::default()
+    }
+
+    /// Whether every card left in `game` is known to a player who has counted the cards.
+    pub fn applies(game: &Game) -> bool {
+        game.player_turn.is_some() && game.last_round && game.deck.is_empty() && game.players.len() == 2
+    }
+
+    /// Solves `game` for `seat`. The game must be in its last round.
+    pub fn solve(&mut self, game: &Game, seat: u8) -> Solution {
+        let mut position = game.fork();
+        let difference = self.search(&position, seat, i32::MIN + 1, i32::MAX);
+        let mut line = vec!();
+        while let Some(best) = self.table.get(&key(&position, seat)).and_then(|e| e.best.clone()) {
+            if position.play(best.clone()).is_err() {
+                break;
+            }
+            line.push(best);
+            // make sure the position is searched with a full window, so its best play is exact
+            self.search(&position, seat, i32::MIN + 1, i32::MAX);
+        }
+        position.discard();
+        Solution {
+            difference,
+            line,
+        }
+    }
+
+    fn search(&mut self, game: &Game, seat: u8, mut alpha: i32, mut beta: i32) -> i32 {
+        if game.player_turn.is_none() {
+            return difference(game, seat);
+        }
+        let key = key(game, seat);
+        if let Some(entry) = self.table.get(&key) {
+            match entry.bound {
+                Bound::Exact => return entry.value,
+                Bound::Lower => alpha = alpha.max(entry.value),
+                Bound::Upper => beta = beta.min(entry.value),
+            }
+            if alpha >= beta {
+                return entry.value;
+            }
+        }
+        let (original_alpha, original_beta) = (alpha, beta);
+        let maximizing = game.player_turn == Some(seat);
+
+        let mut best_value = if maximizing { i32::MIN } else { i32::MAX };
+        let mut best = None;
+        for intent in ordered(game.legal_intents()) {
+            let mut child = game.fork();
+            let _ = child.play(intent.clone());
+            let value = self.search(&child, seat, alpha, beta);
+            child.discard();
+            if (maximizing && value > best_value) || (!maximizing && value < best_value) {
+                best_value = value;
+                best = Some(intent);
+            }
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_value <= original_alpha {
+            Bound::Upper
+        } else if best_value >= original_beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(key, Entry {
+            value: best_value,
+            bound,
+            best,
+        });
+        best_value
+    }
+}
+
+impl std::fmt::Debug for Solver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Solver {{ positions: {} }}", self.table.len())
+    }
+}
+
+/// The strongest opponent: solves the last round exactly and searches with MCTS before it.
+#[derive(Debug, Default)]
+pub struct Expert {
+    mcts: Mcts,
+}
+
+impl Controller for Expert {
+    fn choose(&mut self, game: &Game) -> Option<Intent> {
+        let seat = game.player_turn?;
+        if Solver::applies(game) {
+            Solver::new().solve(game, seat).line.into_iter().next()
+        } else {
+            self.mcts.choose(game)
+        }
+    }
+}
+
+/// A play in the last round that cost points compared to the best play in its position.
+#[derive(Debug, Clone)]
+pub struct Mistake {
+    pub position: usize,
+    pub played: Intent,
+    pub best: Intent,
+    pub lost: i32,
+}
+
+/// Goes through the last round of a finished game and finds the plays of `seat` that lost points
+/// against best play from both sides.
+pub fn analyse(game: &Game, seat: u8) -> Vec<Mistake> {
+    let mut analysis = Analysis::new(game, seat);
+    while analysis.step() {}
+    analysis.mistakes()
+}
+
+/// An analysis as `analyse` makes it, a play at a time, so a window can go on between plays.
+pub struct Analysis {
+    seat: u8,
+    replay: Replay,
+    solver: Solver,
+    mistakes: Vec<Mistake>,
+    /// Whether every play has been looked at, or one could not be made again.
+    over: bool,
+}
+
+impl Analysis {
+    pub fn new(game: &Game, seat: u8) -> Analysis {
+        Analysis {
+            seat,
+            replay: Replay::new(game),
+            solver: Solver::new(),
+            mistakes: vec!(),
+            over: false,
+        }
+    }
+
+    /// Looks at the next play, and tells whether there was one.
+    pub fn step(&mut self) -> bool {
+        let position = self.replay.position();
+        let played = match self.replay.plays().get(position) {
+            Some(played) if !self.over => played.clone(),
+            _ => return false
+        };
+        let game = &self.replay.game;
+        if game.player_turn == Some(self.seat) && Solver::applies(game) {
+            let best = self.solver.solve(game, self.seat);
+            let mut after = game.fork();
+            let _ = after.play(played.clone());
+            let actual = self.solver.solve(&after, self.seat);
+            after.discard();
+            // a position without a line to play has nothing to compare with
+            if let Some(first) = best.line.first().filter(|_| actual.difference < best.difference) {
+                self.mistakes.push(Mistake {
+                    position,
+                    played,
+                    best: first.clone(),
+                    lost: best.difference - actual.difference,
+                });
+            }
+        }
+        // past a play that cannot be made again there is nothing to compare with
+        self.over = self.replay.forward().is_err();
+        true
+    }
+
+    /// The plays found so far that lost points.
+    pub fn mistakes(self) -> Vec<Mistake> {
+        self.replay.game.discard();
+        self.mistakes
+    }
+}
+
+/// The final points of `seat` minus those of the best other player.
+fn difference(game: &Game, seat: u8) -> i32 {
+    let totals: Vec<i32> = game.points().iter().map(|p| p.total() as i32).collect();
+    let best_other = totals.iter().enumerate()
+        .filter(|(i, _)| *i != seat as usize)
+        .map(|(_, t)| *t)
+        .max()
+        .unwrap_or(0);
+    totals[seat as usize] - best_other
+}
+
+/// Takes first, those capturing the most cards before others, then builds, then trails.
+fn ordered(mut intents: Vec<Intent>) -> Vec<Intent> {
+    intents.sort_by_key(|intent| match intent {
+        Intent::Take(_, piles) => -(piles.len() as i32),
+        Intent::Build(..) => 100,
+        Intent::Trail(..) => 200,
+    });
+    intents
+}
+
+fn encode(card: &Card) -> u8 {
+    let suit = match card.suit {
+        Suit::CLUBS => 0,
+        Suit::SPADES => 1,
+        Suit::DIAMONDS => 2,
+        Suit::HEARTS => 3,
+    };
+    suit * 16 + card.value
+}
+
+/// Identifies a position by how the cards are distributed: the hands, the piles on the table and
+/// what they count for, and what in the score piles is still to be counted.
+fn key(game: &Game, seat: u8) -> Vec<u8> {
+    let mut key = vec!(seat, game.player_turn.unwrap_or(255), game.last_capture.unwrap_or(255));
+    for p in &game.players {
+        let mut hand: Vec<u8> = p.hand.cards().iter().map(encode).collect();
+        hand.sort_unstable();
+        key.push(hand.len() as u8);
+        key.append(&mut hand);
+
+        let score = p.score.cards();
+        key.push(score.len() as u8);
+        key.push(score.iter().filter(|c| c.suit == Suit::SPADES).count() as u8);
+        key.push(score.iter().fold(0u8, |bits, c| match c.hand_value() {
+            14 => bits | 1 << (encode(c) / 16),
+            15 => bits | 1 << 4,
+            16 => bits | 1 << 5,
+            _ => bits
+        }));
+        key.push(p.sweeps);
+    }
+    for (i, pile) in game.table.piles().iter().enumerate() {
+        let mut cards: Vec<u8> = pile.cards().iter().map(encode).collect();
+        cards.sort_unstable();
+        key.push(cards.len() as u8);
+        key.append(&mut cards);
+        match game.builds.iter().find(|b| b.pile == *pile) {
+            Some(build) => key.extend_from_slice(&[build.value, build.owner]),
+            None => key.extend_from_slice(&[game.pile_value(i).unwrap_or(0), 255]),
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Deck, Player};
+
+    fn card(suit: Suit, value: u8) -> Card {
+        Card::new(suit, value, Deck::empty())
+    }
+
+    #[test]
+    fn test_solves_last_play() {
+        let mut p1 = Player::new(0, "player1");
+        let mut p2 = Player::new(1, "player2");
+        p1.deal(&mut vec!(card(Suit::HEARTS, 10), card(Suit::CLUBS, 3)));
+        p2.deal(&mut vec!(card(Suit::CLUBS, 10), card(Suit::HEARTS, 5)));
+        let mut game = Game {
+            players: vec!(p1, p2),
+            last_round: true,
+            player_turn: Some(0),
+            ..Game::with_deck(Deck::empty())
+        };
+        game.table.new_pile(vec!(card(Suit::DIAMONDS, 10)));
+        game.table.new_pile(vec!(card(Suit::SPADES, 2)));
+
+        assert!(Solver::applies(&game));
+        let solution = Solver::new().solve(&game, 0);
+        // big casino must be taken now, before the other ten can take it
+        assert_eq!(solution.line[0], Intent::Take(card(Suit::HEARTS, 10), vec!(0)));
+        assert_eq!(solution.line.len(), 4);
+        for intent in &solution.line {
+            game.play(intent.clone()).unwrap();
+        }
+        assert_eq!(game.player_turn, None);
+        assert_eq!(difference(&game, 0), solution.difference);
+    }
+
+    #[test]
+    fn test_analyse_finds_costly_plays() {
+        use rand::SeedableRng;
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Game::with_deck(Deck::shuffled(&mut rand::rngs::StdRng::seed_from_u64(3)))
+        };
+        game.start();
+        // trail whenever possible, which gives away points
+        while game.player_turn.is_some() {
+            let intent = game.legal_intents().pop().unwrap();
+            game.play(intent).unwrap();
+        }
+        for seat in 0..2 {
+            for mistake in analyse(&game, seat) {
+                assert!(mistake.lost > 0);
+                assert_ne!(mistake.played, mistake.best);
+            }
+        }
+        game.discard();
+    }
+}
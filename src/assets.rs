@@ -0,0 +1,177 @@
+//! Asset packs: the card faces, card backs, table colour and font the window draws with. A pack
+//! is a folder with a `manifest.txt`, found in the `assets` folder near where the window is
+//! started or near the executable. The default pack is `assets` itself, and others are folders
+//! in it. Paths in a manifest are relative to its folder:
+//!
+//! ```text
+//! # the faces in one image, cards 180 by 270 and 15 apart, a row for each suit
+//! sheet cards_deck-half.png 180 270 15
+//! rows spades hearts diamonds clubs
+//! back plain back-sized.png
+//! table 0.3 0.6 0.3
+//! font FiraSans-Regular.ttf
+//! ```
+//!
+//! Instead of `sheet` and `rows`, `files <pattern>` gives an image for each card, e.g.
+//! `files faces/{suit}{value}.png`, where `{suit}` is the suit as in the notation, e.g. `S`, and
+//! `{value}` the value from 1 to 13. A pack has at least one `back`, the first one by default.
+//!
+//! A sheet may be an `.svg` drawing, with the sizes in its units. It is then drawn again whenever
+//! the cards are shown at another size, so they stay sharp.
+
+use crate::types::{Card, Suit};
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST: &str = "manifest.txt";
+
+/// Where the faces of the cards are drawn from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Faces {
+    /// One image of every card, a row for each suit in `rows` and a column for each value from
+    /// ace to king, each card `width` by `height` and `gap` from the next and from the edges.
+    Sheet {
+        image: PathBuf,
+        width: f64,
+        height: f64,
+        gap: f64,
+        rows: Vec<Suit>,
+    },
+    /// An image for each card, named by a pattern.
+    Files(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pack {
+    pub dir: PathBuf,
+    pub faces: Faces,
+    /// The card backs to choose from, by name.
+    pub backs: Vec<(String, PathBuf)>,
+    /// The back the cards are shown with.
+    pub back: usize,
+    pub table: [f32; 4],
+    pub font: PathBuf,
+}
+
+impl Pack {
+    /// Finds the pack `name`, or the default pack, and reads its manifest.
+    pub fn find(name: Option<&str>) -> Result<Pack, String> {
+        let near_exe = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+        let assets = find_folder::Search::ParentsThenKids(3, 3).for_folder("assets")
+            .or_else(|e| match near_exe {
+                Some(dir) => find_folder::Search::Parents(3).of(dir).for_folder("assets"),
+                None => Err(e),
+            })
+            .map_err(|e| format!("could not find the assets folder: {:?}", e))?;
+        let dir = match name {
+            Some(name) => assets.join(name),
+            None => assets,
+        };
+        let manifest = dir.join(MANIFEST);
+        let text = std::fs::read_to_string(&manifest).map_err(|e| format!("could not read {}: {}", manifest.display(), e))?;
+        Pack::parse(&dir, &text)
+    }
+
+    /// Reads the manifest of a pack in `dir`.
+    pub fn parse(dir: &Path, text: &str) -> Result<Pack, String> {
+        let mut sheet = None;
+        let mut rows = None;
+        let mut files = None;
+        let mut backs = vec!();
+        let mut table = [0.3, 0.6, 0.3, 1.0];
+        let mut font = None;
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let mut words = line.split_whitespace();
+            let word = words.next().unwrap();
+            let rest: Vec<&str> = words.collect();
+            let numbers = || rest.iter().skip(1).map(|n| n.parse::<f64>().map_err(|_| format!("invalid number '{}'", n))).collect::<Result<Vec<f64>, String>>();
+            match (word, rest.len()) {
+                ("sheet", 4) => {
+                    let n = numbers()?;
+                    sheet = Some((dir.join(rest[0]), n[0], n[1], n[2]));
+                },
+                ("rows", 4) => rows = Some(rest.iter().map(|suit| match *suit {
+                    "clubs" => Ok(Suit::CLUBS),
+                    "spades" => Ok(Suit::SPADES),
+                    "diamonds" => Ok(Suit::DIAMONDS),
+                    "hearts" => Ok(Suit::HEARTS),
+                    _ => Err(format!("unknown suit '{}'", suit)),
+                }).collect::<Result<Vec<Suit>, String>>()?),
+                ("files", 1) => files = Some(dir.join(rest[0]).to_string_lossy().into_owned()),
+                ("back", 2) => backs.push((rest[0].to_string(), dir.join(rest[1]))),
+                ("table", 3) => {
+                    let colour: Vec<f32> = rest.iter().map(|c| c.parse().map_err(|_| format!("invalid colour '{}'", c))).collect::<Result<_, String>>()?;
+                    table = [colour[0], colour[1], colour[2], 1.0];
+                },
+                ("font", 1) => font = Some(dir.join(rest[0])),
+                _ => return Err(format!("invalid line '{}'", line)),
+            }
+        }
+
+        let faces = match (sheet, rows, files) {
+            (Some((image, width, height, gap)), Some(rows), None) => Faces::Sheet { image, width, height, gap, rows },
+            (None, None, Some(pattern)) => Faces::Files(pattern),
+            _ => return Err("a pack has either a sheet and its rows, or files".to_string()),
+        };
+        if backs.is_empty() {
+            return Err("a pack has at least one back".to_string());
+        }
+        Ok(Pack {
+            dir: dir.to_path_buf(),
+            faces,
+            backs,
+            back: 0,
+            table,
+            font: font.ok_or("a pack has a font")?,
+        })
+    }
+
+    /// Shows the cards with the back named `name`.
+    pub fn choose_back(&mut self, name: &str) -> Result<(), String> {
+        self.back = self.backs.iter().position(|(n, _)| n == name)
+            .ok_or(format!("no back '{}', only {}", name, self.backs.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>().join(", ")))?;
+        Ok(())
+    }
+
+    pub fn back(&self) -> &Path {
+        &self.backs[self.back].1
+    }
+
+    /// The image the face of `card` is in, and the part of it that is the card, if it is not
+    /// all of it.
+    pub fn face(&self, card: &Card) -> (PathBuf, Option<[f64; 4]>) {
+        match &self.faces {
+            Faces::Sheet { image, width, height, gap, rows } => {
+                let row = rows.iter().position(|s| *s == card.suit).unwrap_or(0) as f64;
+                let col = (card.value - 1) as f64;
+                let rect = [gap + (width + gap) * col, gap + (height + gap) * row, *width, *height];
+                (image.clone(), Some(rect))
+            },
+            Faces::Files(pattern) => {
+                let suit = card.to_string().split(':').next().unwrap_or_default().to_string();
+                (PathBuf::from(pattern.replace("{suit}", &suit).replace("{value}", &card.value.to_string())), None)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        let card = |s: &str| s.parse::<Card>().unwrap();
+        let text = std::fs::read_to_string(Path::new("assets").join(MANIFEST)).unwrap();
+        let mut pack = Pack::parse(Path::new("assets"), &text).unwrap();
+        // the sheet the window has always used: spades on top, aces on the left
+        assert_eq!(pack.face(&card("S:1")), (PathBuf::from("assets/cards_deck.svg"), Some([30.0, 30.0, 360.0, 540.0])));
+        assert_eq!(pack.face(&card("C:13")).1, Some([4710.0, 1740.0, 360.0, 540.0]));
+        assert_eq!(pack.back(), Path::new("assets/back-sized.png"));
+        assert!(pack.choose_back("tartan").is_err());
+
+        let pack = Pack::parse(Path::new("art"), "files faces/{suit}{value}.png\nback red red.png\nback blue blue.png\nfont font.ttf").unwrap();
+        assert_eq!(pack.face(&card("H:12")), (PathBuf::from("art/faces/H12.png"), None));
+        assert_eq!(pack.backs.len(), 2);
+        assert!(Pack::parse(Path::new("art"), "back red red.png\nfont font.ttf").is_err());
+    }
+}
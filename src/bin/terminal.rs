@@ -0,0 +1,106 @@
+//! Plays kasino in a terminal, e.g. over SSH or where there is no OpenGL.
+//!
+//! ```text
+//! terminal [--ai <seat>[:<controller>]]... [--color] [--no-undo]
+//! ```
+//!
+//! Seats are given as to the window, numbered from 1; a seat past the second adds the seats up to
+//! it. The player in turn is shown the table and their legal moves, and plays by typing the
+//! number of a move or the move itself, e.g. `take H:10 0`. `undo` takes back the last play and
+//! `quit` leaves the game.
+
+use std::io::{self, BufRead, Write};
+use rcards::events::Event;
+use rcards::graphic::Ui;
+use rcards::setup::{Setup, MAX_SEATS};
+use rcards::terminal;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let mut setup = Setup::default();
+    for pair in args.windows(2).filter(|pair| pair[0] == "--ai") {
+        let (seat, spec) = pair[1].split_once(':').unwrap_or((&pair[1], "medium"));
+        let number = match seat.parse::<usize>() {
+            Ok(number) if (1..=MAX_SEATS).contains(&number) => number,
+            _ => usage(&format!("unknown seat '{}', seats are numbered from 1 to {}", seat, MAX_SEATS))
+        };
+        if number > setup.seats.len() {
+            setup.set_seats(number);
+        }
+        setup.seats[number - 1].ai = Some(spec.to_string());
+    }
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    let color = flag("--color");
+
+    let mut game = setup.game().unwrap_or_else(|e| usage(&e));
+    game.undo_enabled = !flag("--no-undo");
+    let humans = game.players.iter().filter(|p| p.controller.is_none()).count();
+    let names: Vec<String> = game.players.iter().map(|p| p.name.clone()).collect();
+    // players sharing the terminal pass it on between turns
+    let mut ui = Ui {
+        privacy_screen: humans > 1,
+        ..Default::default()
+    };
+    game.subscribe(|e: &Event| match e {
+        Event::CardDealt(..) | Event::TurnChanged(_) => {},
+        e => println!("{}", e),
+    });
+    game.start();
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock().lines();
+    while let Some(seat) = game.player_turn {
+        if game.advance() {
+            continue;
+        }
+        if ui.screened(&game) {
+            // clear the screen, so the last player's hand is gone
+            print!("\x1b[2J\x1b[H{}, press enter to see your hand ", names[seat as usize]);
+            io::stdout().flush().unwrap();
+            if input.next().is_none() {
+                return;
+            }
+            ui.revealed = Some(seat);
+        }
+
+        println!();
+        for line in terminal::render(&game.view(seat), color) {
+            println!("{}", line);
+        }
+        let intents = game.legal_intents();
+        for line in terminal::choices(&intents) {
+            println!("{}", line);
+        }
+        print!("{}> ", names[seat as usize]);
+        io::stdout().flush().unwrap();
+
+        let line = match input.next() {
+            Some(Ok(line)) => line,
+            _ => return,
+        };
+        match line.trim() {
+            "quit" => return,
+            "undo" => if let Err(e) = game.undo() {
+                println!("{:?}", e);
+            },
+            typed => match terminal::choose(typed, &intents) {
+                Ok(intent) => if let Err(e) = game.play(intent) {
+                    println!("{:?}", e);
+                },
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+
+    println!();
+    for line in terminal::score(&names, &game.points()) {
+        println!("{}", line);
+    }
+}
+
+/// Tells what was wrong with the command line and how to give it, and exits.
+fn usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("usage: terminal [--ai <seat>[:<controller>]]... [--color] [--no-undo]");
+    std::process::exit(2);
+}
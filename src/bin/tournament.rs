@@ -0,0 +1,169 @@
+//! Plays computer opponents against each other without a window and reports how they fare.
+//!
+//! ```text
+//! tournament [--deals <n>] [--seed <seed>] <spec> <spec> [<spec> ...]
+//! ```
+//!
+//! Every spec is a controller as given to `--ai` in the window, e.g. `hard`, `mcts:500` or
+//! `bot:./my-bot`. Each deal is dealt from a seeded shuffle and played once for every seating,
+//! with the controllers moved one seat on each time, so no controller profits from its seat or
+//! from a lucky deal.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rcards::ai::{self, Controller};
+use rcards::kasino::Points;
+use rcards::types::{Deck, Game, Player};
+
+const CATEGORIES: [&str; 7] = ["cards", "spades", "big casino", "little casino", "aces", "sweeps", "total"];
+
+/// Results of one controller over the whole tournament.
+struct Standing {
+    spec: String,
+    controller: Option<Box<dyn Controller>>,
+    games: u32,
+    wins: u32,
+    draws: u32,
+    /// Sum and sum of squares of the points in each category.
+    points: [(f64, f64); 7],
+}
+
+impl Standing {
+    fn record(&mut self, points: &Points, result: Ordering) {
+        self.games += 1;
+        match result {
+            Ordering::Won => self.wins += 1,
+            Ordering::Drawn => self.draws += 1,
+            Ordering::Lost => {},
+        }
+        let values = [points.cards, points.spades, points.big_casino, points.little_casino,
+            points.aces, points.sweeps, points.total()];
+        for (sums, value) in self.points.iter_mut().zip(values.iter()) {
+            let value = f64::from(*value);
+            sums.0 += value;
+            sums.1 += value * value;
+        }
+    }
+
+    /// Wins count whole and draws half.
+    fn win_rate(&self) -> (f64, f64) {
+        let n = f64::from(self.games);
+        let rate = (f64::from(self.wins) + 0.5 * f64::from(self.draws)) / n;
+        (rate, 1.96 * (rate * (1.0 - rate) / n).sqrt())
+    }
+
+    fn average(&self, category: usize) -> (f64, f64) {
+        let n = f64::from(self.games);
+        let (sum, squares) = self.points[category];
+        let mean = sum / n;
+        let variance = if self.games > 1 { (squares - sum * mean) / (n - 1.0) } else { 0.0 };
+        (mean, 1.96 * (variance.max(0.0) / n).sqrt())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Ordering {
+    Won,
+    Drawn,
+    Lost,
+}
+
+/// Tells what was wrong with the arguments and how to give them, and exits.
+fn usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("usage: tournament [--deals <n>] [--seed <seed>] <spec> <spec> [<spec> ...]");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut deals = 100u64;
+    let mut seed = 0u64;
+    let mut specs = vec!();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--deals" => deals = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage("--deals takes a number")),
+            "--seed" => seed = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage("--seed takes a number")),
+            _ => specs.push(arg),
+        }
+    }
+    if specs.len() < 2 {
+        usage("at least two controllers play");
+    }
+
+    let mut standings: Vec<Standing> = specs.iter().map(|spec| Standing {
+        spec: spec.clone(),
+        controller: Some(ai::from_spec(spec).unwrap_or_else(|e| usage(&e))),
+        games: 0,
+        wins: 0,
+        draws: 0,
+        points: [(0.0, 0.0); 7],
+    }).collect();
+
+    let seats = standings.len();
+    for deal in 0..deals {
+        for rotation in 0..seats {
+            // the controller in seat `s` is the one `rotation` places further on
+            let entry = |seat: usize| (seat + rotation) % seats;
+            let players = (0..seats).map(|seat| {
+                let mut player = Player::new(seat as u8, &standings[entry(seat)].spec);
+                player.controller = standings[entry(seat)].controller.take();
+                player
+            }).collect();
+
+            let mut game = Game {
+                players,
+                undo_enabled: false,
+                ..Default::default()
+            };
+            Deck::release(game.deck);
+            game.deck = Deck::shuffled(&mut StdRng::seed_from_u64(seed.wrapping_add(deal)));
+            game.start();
+            while game.advance() {}
+            if let Some(seat) = game.player_turn {
+                eprintln!("deal {} stalled with {} in turn", deal, game.players[seat as usize].name);
+            }
+
+            let points = game.points();
+            let best = points.iter().map(Points::total).max().unwrap_or(0);
+            let winners = points.iter().filter(|p| p.total() == best).count();
+            for seat in 0..seats {
+                let result = match (points[seat].total() == best, winners) {
+                    (true, 1) => Ordering::Won,
+                    (true, _) => Ordering::Drawn,
+                    _ => Ordering::Lost,
+                };
+                let standing = &mut standings[entry(seat)];
+                standing.record(&points[seat], result);
+                standing.controller = game.players[seat].controller.take();
+            }
+            game.discard();
+        }
+        eprint!("\r{}/{} deals", deal + 1, deals);
+    }
+    eprintln!();
+
+    println!("{} deals from seed {}, each played in {} seatings ({} games)", deals, seed, seats, deals * seats as u64);
+    println!("95% confidence intervals are given after ±\n");
+    println!("{:<16}{:>8}{:>8}{:>8}{:>18}", "controller", "wins", "draws", "losses", "win rate");
+    for s in &standings {
+        let (rate, interval) = s.win_rate();
+        println!("{:<16}{:>8}{:>8}{:>8}{:>10.1}% ±{:>4.1}%", s.spec, s.wins, s.draws, s.games - s.wins - s.draws,
+                 100.0 * rate, 100.0 * interval);
+    }
+
+    println!("\naverage points per game");
+    print!("{:<16}", "controller");
+    for category in CATEGORIES.iter() {
+        print!("{:>16}", category);
+    }
+    println!();
+    for s in &standings {
+        print!("{:<16}", s.spec);
+        for category in 0..CATEGORIES.len() {
+            let (mean, interval) = s.average(category);
+            print!("{:>16}", format!("{:.2} ±{:.2}", mean, interval));
+        }
+        println!();
+    }
+}
@@ -0,0 +1,185 @@
+use crate::types::{Game, Card, Intent};
+use std::fmt;
+
+/// A change to the game as it happens. Players are referred to by their seat number.
+#[derive(Debug, Clone)]
+pub enum Event {
+    CardDealt(Option<u8>, Card),
+    CardTrailed(u8, Card),
+    PileCaptured(u8, Card, Vec<Card>),
+    /// The cards left on the table when the game ends, which go to the player who captured last.
+    TableCleared(u8, Vec<Card>),
+    BuildCreated(u8, Card, u8),
+    SweepScored(u8),
+    TurnChanged(Option<u8>),
+    RoundEnded(u8),
+    GameEnded,
+    Undone(Intent),
+    /// The computer came up with a play it may not make for the seat, and made the first legal
+    /// one instead.
+    IllegalPlay(u8),
+}
+
+/// Receives every event emitted by a game it is subscribed to.
+pub trait Observer {
+    fn notify(&mut self, event: &Event);
+}
+
+impl<F: FnMut(&Event)> Observer for F {
+    fn notify(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+impl Game {
+    pub fn subscribe<O: Observer + 'static>(&mut self, observer: O) {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub(crate) fn emit(&mut self, event: Event) {
+        for observer in &mut self.observers {
+            observer.notify(&event);
+        }
+    }
+}
+
+impl Event {
+    /// The event as `seat` may see it: cards dealt to other seats are hidden. Spectators have no
+    /// seat.
+    pub fn seen_by(&self, seat: Option<u8>) -> Event {
+        match self {
+            Event::CardDealt(Some(player), _) if seat != Some(*player) => Event::CardDealt(Some(*player), Card::hidden()),
+            event => event.clone(),
+        }
+    }
+
+    /// The event as a line of the network protocol.
+    pub fn to_line(&self) -> String {
+        let seat = |seat: &Option<u8>| seat.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+        match self {
+            Event::CardDealt(player, card) => format!("dealt {} {}", seat(player), card),
+            Event::CardTrailed(player, card) => format!("trailed {} {}", player, card),
+            Event::PileCaptured(player, card, cards) => {
+                format!("captured {} {}{}", player, card, cards.iter().map(|c| format!(" {}", c)).collect::<String>())
+            },
+            Event::TableCleared(player, cards) => format!("cleared {}{}", player, cards.iter().map(|c| format!(" {}", c)).collect::<String>()),
+            Event::BuildCreated(player, card, value) => format!("built {} {} {}", player, card, value),
+            Event::SweepScored(player) => format!("swept {}", player),
+            Event::TurnChanged(player) => format!("turn {}", seat(player)),
+            Event::RoundEnded(round) => format!("round {}", round),
+            Event::GameEnded => "over".to_string(),
+            Event::Undone(intent) => format!("undone {}", intent),
+            Event::IllegalPlay(player) => format!("illegal {}", player),
+        }
+    }
+
+    pub fn from_line(line: &str) -> Result<Event, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let malformed = || format!("malformed event '{}'", line);
+        let number = |i: usize| words.get(i).and_then(|w| w.parse::<u8>().ok()).ok_or_else(malformed);
+        let seat = |i: usize| words.get(i).and_then(|w| w.parse::<u8>().ok());
+        let card = |i: usize| words.get(i).ok_or_else(malformed).and_then(|w| w.parse::<Card>());
+        match words.first() {
+            Some(&"dealt") => Ok(Event::CardDealt(seat(1), card(2)?)),
+            Some(&"trailed") => Ok(Event::CardTrailed(number(1)?, card(2)?)),
+            Some(&"captured") => {
+                let cards = (3..words.len()).map(card).collect::<Result<Vec<Card>, String>>()?;
+                Ok(Event::PileCaptured(number(1)?, card(2)?, cards))
+            },
+            Some(&"cleared") => {
+                let cards = (2..words.len()).map(card).collect::<Result<Vec<Card>, String>>()?;
+                Ok(Event::TableCleared(number(1)?, cards))
+            },
+            Some(&"built") => Ok(Event::BuildCreated(number(1)?, card(2)?, number(3)?)),
+            Some(&"swept") => Ok(Event::SweepScored(number(1)?)),
+            Some(&"turn") => Ok(Event::TurnChanged(seat(1))),
+            Some(&"round") => Ok(Event::RoundEnded(number(1)?)),
+            Some(&"over") => Ok(Event::GameEnded),
+            Some(&"undone") => Ok(Event::Undone(words[1..].join(" ").parse()?)),
+            Some(&"illegal") => Ok(Event::IllegalPlay(number(1)?)),
+            _ => Err(malformed()),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::CardDealt(Some(player), card) => write!(f, "player {} is dealt {}", player, card),
+            Event::CardDealt(None, card) => write!(f, "{} is dealt to the table", card),
+            Event::CardTrailed(player, card) => write!(f, "player {} trails {}", player, card),
+            Event::PileCaptured(player, card, cards) => {
+                write!(f, "player {} takes", player)?;
+                for c in cards {
+                    write!(f, " {}", c)?;
+                }
+                write!(f, " with {}", card)
+            },
+            Event::TableCleared(player, cards) => {
+                write!(f, "player {} gets what is left on the table:", player)?;
+                for c in cards {
+                    write!(f, " {}", c)?;
+                }
+                Ok(())
+            },
+            Event::BuildCreated(player, card, value) => write!(f, "player {} builds {} with {}", player, value, card),
+            Event::SweepScored(player) => write!(f, "player {} sweeps the table", player),
+            Event::TurnChanged(Some(player)) => write!(f, "player {} is in turn", player),
+            Event::TurnChanged(None) => write!(f, "no one is in turn"),
+            Event::RoundEnded(round) => write!(f, "round {} has ended", round),
+            Event::GameEnded => write!(f, "the game has ended"),
+            Event::Undone(intent) => write!(f, "{} was taken back", intent),
+            Event::IllegalPlay(player) => write!(f, "player {} made an illegal play", player),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Player;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_observer_receives_events() {
+        let events = Rc::new(RefCell::new(vec!()));
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        {
+            let events = events.clone();
+            game.subscribe(move |e: &Event| events.borrow_mut().push(e.clone()));
+        }
+        game.start();
+        let dealt = events.borrow().iter().filter(|e| matches!(e, Event::CardDealt(..))).count();
+        assert_eq!(dealt, 12);
+
+        let card = game.players[0].hand.cards()[0].clone();
+        game.play(Intent::Trail(card)).unwrap();
+        let events = events.borrow();
+        assert!(matches!(events[events.len() - 2], Event::CardTrailed(0, _)));
+        assert!(matches!(events[events.len() - 1], Event::TurnChanged(Some(1))));
+    }
+
+    #[test]
+    fn test_events_as_seen_by_others() {
+        let card: Card = "S:2".parse().unwrap();
+        let dealt = Event::CardDealt(Some(0), card.clone());
+        assert_eq!(dealt.seen_by(Some(0)).to_line(), "dealt 0 S:2");
+        assert_eq!(dealt.seen_by(Some(1)).to_line(), "dealt 0 ??");
+        assert_eq!(dealt.seen_by(None).to_line(), "dealt 0 ??");
+
+        let captured = Event::PileCaptured(1, card.clone(), vec!(card));
+        let line = captured.seen_by(None).to_line();
+        assert_eq!(line, "captured 1 S:2 S:2");
+        assert_eq!(Event::from_line(&line).unwrap().to_line(), line);
+        assert!(Event::from_line("built 1").is_err());
+
+        let cleared = Event::TableCleared(0, vec!("H:3".parse().unwrap(), "C:9".parse().unwrap()));
+        assert_eq!(cleared.to_line(), "cleared 0 H:3 C:9");
+        assert!(matches!(Event::from_line("cleared 0 H:3 C:9").unwrap(), Event::TableCleared(0, cards) if cards.len() == 2));
+        assert!(matches!(Event::from_line("illegal 1").unwrap(), Event::IllegalPlay(1)));
+    }
+}
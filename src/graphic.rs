@@ -1,24 +1,31 @@
-use crate::types::{Game, Card, Suit, Player, Deck, PlayerInteraction};
+use crate::types::{Game, Card, Deck, DeckRef, Intent, PlayerInteraction, Viewer};
 use crate::kasino::IntentError;
-use sprite::{Sprite, Scene};
-use sdl2_window::Sdl2Window;
+use crate::layout::{Layout, CARD_WIDTH, CARD_HEIGHT};
+use crate::assets::{Pack, Faces};
+use crate::svg::Drawing;
+use crate::hit;
+use crate::keyboard::{self, Focus};
+use crate::menu::{self, Menu};
+use sprite::{Sprite, Scene, EaseFunction, Ease, MoveTo, ScaleTo};
+use ai_behavior::Action;
 use std::rc::Rc;
-use std::path::Path;
-use std::convert::Into;
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
-use graphics::rectangle::rectangle_by_corners;
-use opengl_graphics::{GlGraphics, Texture};
+use graphics::ImageSize;
+use opengl_graphics::{GlGraphics, GlyphCache, Texture, CreateTexture, Format};
 use std::borrow::ToOwned;
-use piston::input::{UpdateArgs, RenderArgs};
+use piston::input::RenderArgs;
 use piston_window::TextureSettings;
-use piston::Key;
 use std::iter::IntoIterator;
 use piston::window::Size;
 use uuid::Uuid;
-use std::cell::RefCell;
-use std::sync::{Arc, RwLock};
+use std::sync::RwLock;
+use std::sync::mpsc::Sender;
 
 
+/// The height of a line of text over the table.
+const LINE_HEIGHT: f64 = 24.0;
+
 lazy_static! {
     static ref SPRITES: RwLock<HashMap<SpriteRef, SpriteInfo>> = RwLock::new(HashMap::new());
 }
@@ -33,9 +40,9 @@ impl SpriteRef {
         sprite_ref
     }
 
-    pub fn get_info<'l>(&self) -> SpriteInfo {
+    pub fn get_info(&self) -> SpriteInfo {
         let guard = SPRITES.read().unwrap();
-        guard.get(&self).unwrap().clone()
+        guard.get(self).unwrap().clone()
     }
 }
 
@@ -46,230 +53,826 @@ impl std::convert::From<&Uuid> for SpriteRef {
 }
 
 pub struct GraphicsEnv {
-    textures: HashMap<TextureKind, Rc<Texture>>,
+    back: Rc<Texture>,
+    /// The face of every card: the texture it is in, and the part of it that is the card.
+    faces: Vec<(Card, Rc<Texture>, Option<[f64; 4]>)>,
+    /// Faces drawn from SVG, which are rasterized again when cards are shown at another size.
+    vector: Option<VectorFaces>,
+    table: [f32; 4],
+    glyphs: GlyphCache<'static>,
     gl: GlGraphics,
 }
 
-#[derive(Eq, PartialEq, Hash)]
-pub enum TextureKind {
-    BACK,
-    CARDS
+/// A sheet of faces drawn from SVG, and the rasterized sheets of the sizes it has been shown at.
+struct VectorFaces {
+    drawing: Drawing,
+    /// The width of a card in the drawing, and the part of the drawing that is every card.
+    card_width: f64,
+    rects: Vec<(Card, [f64; 4])>,
+    /// Sheets by the width of a card in them, the one last used first.
+    sheets: Vec<(u32, Rc<Texture>)>,
 }
 
+/// How many rasterized sheets are kept, e.g. to go back and forth between sizes.
+const SHEETS_KEPT: usize = 4;
+
 #[derive(Debug, Clone)]
 pub struct SpriteInfo {
     pub card: Card,
 }
 
+/// A card being dragged from the hand of the player in turn.
+#[derive(Debug, Clone)]
+pub struct Drag {
+    pub sprite: SpriteRef,
+    /// Where the card was grabbed, from its centre.
+    grab: [f64; 2],
+    start: [f64; 2],
+    /// Whether the card has been moved far enough to be dropped, rather than clicked.
+    moved: bool,
+}
+
+/// How a card is marked in the window, by a frame around it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Highlight {
+    /// The card under the pointer.
+    Hovered,
+    Selected,
+    /// A pile the selected card can take.
+    Takes,
+    /// A pile the selected card can build on.
+    BuildsOn,
+    /// A pile that is a build.
+    Build,
+    /// The card under the keyboard cursor.
+    Focused,
+}
+
+impl Highlight {
+    /// The colour of the frame, and how far it is from the card.
+    fn frame(self) -> ([f32; 4], f64) {
+        match self {
+            Highlight::Hovered => ([1.0, 1.0, 1.0, 0.5], 6.0),
+            Highlight::Selected => ([1.0, 0.85, 0.0, 1.0], 3.0),
+            Highlight::Takes => ([0.2, 0.9, 0.2, 1.0], 3.0),
+            Highlight::BuildsOn => ([0.3, 0.6, 1.0, 1.0], 3.0),
+            Highlight::Build => ([1.0, 0.5, 0.0, 1.0], 3.0),
+            Highlight::Focused => ([0.0, 0.9, 0.9, 1.0], 8.0),
+        }
+    }
+}
+
+/// The tint of the cards of the pile a dragged card would be dropped on.
+const DROP_TINT: (f32, f32, f32) = (0.6, 1.0, 0.6);
+
 
 
 impl GraphicsEnv {
-    pub fn new(gl: GlGraphics) -> Self {
-        // load textures
-        let mut textures = HashMap::new();
-        let back = load_texture(&Path::new("assets/back-sized.png"));
-        let front = load_texture(&Path::new("assets/cards_deck-half.png"));
+    /// Loads the art of `pack`, each image once. Faces drawn as SVG are rasterized for cards the
+    /// size of the layout's cards until `fit_faces` is told otherwise.
+    pub fn new(gl: GlGraphics, pack: &Pack) -> Self {
+        let mut textures: HashMap<PathBuf, Rc<Texture>> = HashMap::new();
+        let mut load = |path: PathBuf| textures.entry(path).or_insert_with_key(|path| load_texture(path)).clone();
+        let back = load(pack.back().to_path_buf());
+        let vector = match &pack.faces {
+            Faces::Sheet { image, width, .. } if image.extension().is_some_and(|e| e == "svg") => Some(VectorFaces {
+                drawing: Drawing::load(image).unwrap(),
+                card_width: *width,
+                rects: Deck::standard().into_iter().map(|card| {
+                    let rect = pack.face(&card).1.unwrap();
+                    (card, rect)
+                }).collect(),
+                sheets: vec!(),
+            }),
+            _ => None,
+        };
+        let faces = match vector {
+            // filled in by fit_faces below
+            Some(_) => vec!(),
+            None => Deck::standard().into_iter().map(|card| {
+                let (image, rect) = pack.face(&card);
+                let texture = load(image);
+                (card, texture, rect)
+            }).collect(),
+        };
+        let glyphs = GlyphCache::new(&pack.font, (), TextureSettings::new()).unwrap();
 
-        textures.insert(TextureKind::BACK, back.clone());
-        textures.insert(TextureKind::CARDS, front.clone());
+        let mut env = GraphicsEnv {
+            gl, back, faces, vector, glyphs,
+            table: pack.table,
+        };
+        env.fit_faces(CARD_WIDTH);
+        env
+    }
 
-        GraphicsEnv {
-            gl, textures,
+    /// Rasterizes faces drawn as SVG for cards `width` pixels wide, unless they are already.
+    /// Sheets of the last few sizes are kept, so they are not drawn again.
+    pub fn fit_faces(&mut self, width: f64) {
+        let vector = match self.vector.as_mut() {
+            Some(vector) => vector,
+            None => return
+        };
+        let pixels = (width.round() as u32).max(1);
+        if vector.sheets.first().is_some_and(|(p, _)| *p == pixels) {
+            return;
         }
+        let scale = pixels as f64 / vector.card_width;
+        let sheet = match vector.sheets.iter().position(|(p, _)| *p == pixels) {
+            Some(i) => vector.sheets.remove(i),
+            None => {
+                let (memory, w, h) = vector.drawing.rasterize(scale);
+                let texture = Texture::create(&mut (), Format::Rgba8, &memory, [w, h], &TextureSettings::new()).unwrap();
+                (pixels, Rc::new(texture))
+            }
+        };
+        self.faces = vector.rects.iter().map(|(card, [x, y, w, h])| {
+            (card.clone(), sheet.1.clone(), Some([x * scale, y * scale, w * scale, h * scale]))
+        }).collect();
+        vector.sheets.insert(0, sheet);
+        vector.sheets.truncate(SHEETS_KEPT);
     }
 
-    pub fn get_texture(&self, kind: TextureKind) -> Rc<Texture> {
-        self.textures.get(&kind).unwrap().to_owned()
+    pub fn back(&self) -> Rc<Texture> {
+        self.back.clone()
+    }
+
+    /// The texture the face of `card` is in, and the part of it that is the card.
+    pub fn face(&self, card: &Card) -> (Rc<Texture>, Option<[f64; 4]>) {
+        let (_, texture, rect) = self.faces.iter().find(|(c, _, _)| c == card).unwrap();
+        (texture.clone(), *rect)
+    }
+
+    /// Draws the menu shown over an empty table: its title, its lines one under another with the
+    /// one under the cursor marked, and the message under them.
+    pub fn render_menu(&mut self, menu: &Menu, args: &RenderArgs) {
+        let GraphicsEnv { gl, glyphs, table, .. } = self;
+        gl.draw(args.viewport(), |c, g| {
+            use graphics::*;
+
+            clear(*table, g);
+            let left = 60.0;
+            let _ = text([1.0, 1.0, 1.0, 1.0], 32, menu.title(), glyphs, c.transform.trans(left, menu::TOP - menu::LINE_HEIGHT * 1.5), g);
+            let lines = menu.lines();
+            for (i, line) in lines.iter().enumerate() {
+                // lines are centred on their place, so they are clicked where they are drawn
+                let y = menu::TOP + menu::LINE_HEIGHT * i as f64;
+                if i == menu.cursor() {
+                    rectangle([0.0, 0.0, 0.0, 0.3], [left - 10.0, y - menu::LINE_HEIGHT / 2.0, 400.0, menu::LINE_HEIGHT], c.transform, g);
+                }
+                let color = if i == menu.cursor() { [1.0, 0.85, 0.0, 1.0] } else { [1.0, 1.0, 1.0, 1.0] };
+                let _ = text(color, 22, line, glyphs, c.transform.trans(left, y + 8.0), g);
+            }
+            if let Some(message) = &menu.message {
+                let y = menu::TOP + menu::LINE_HEIGHT * (lines.len() as f64 + 0.5);
+                let _ = text([1.0, 1.0, 1.0, 1.0], 18, message, glyphs, c.transform.trans(left, y), g);
+            }
+        });
     }
 }
 
-impl Game {
-    pub fn prepare(&mut self, scene: &mut Scene<Texture>) {
+/// What the window shows of a game, and how the game is being played at it. The game itself
+/// holds only the state of play, so it can be forked, snapshotted and viewed without this.
+pub struct Ui {
+    pub graphics_env: Option<GraphicsEnv>,
+    pub interactions: Vec<PlayerInteraction>,
+    pub reveal_all: bool,
+    /// Whose eyes the window shows the game through.
+    pub viewer: Viewer,
+    /// Where plays made at the window are sent, instead of being played, when the game is owned
+    /// by a server.
+    pub submit: Option<Sender<Intent>>,
+    /// Covers the hands whenever the turn changes, so players sharing the window do not see each
+    /// other's hand, until the player in turn reveals theirs.
+    pub privacy_screen: bool,
+    /// The seat that last revealed its hand, which is covered again once another seat is in turn.
+    pub revealed: Option<u8>,
+    /// What the window last has to say, e.g. why a play is not allowed, or what went on at the
+    /// server. Each of its lines is a line of the text over the table.
+    pub message: Option<String>,
+    /// Seconds a card takes to move to a new place in the window. Cards are placed at once when
+    /// it is 0.
+    pub animation_time: f64,
+    /// The card being dragged in the window.
+    pub drag: Option<Drag>,
+    /// The card under the pointer, if the window may do anything with it.
+    pub hovered: Option<SpriteRef>,
+    /// The card under the keyboard cursor.
+    pub focus: Option<Focus>,
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Ui {
+            graphics_env: None,
+            interactions: vec!(),
+            reveal_all: false,
+            viewer: Viewer::InTurn,
+            submit: None,
+            privacy_screen: false,
+            revealed: None,
+            message: None,
+            animation_time: 0.3,
+            drag: None,
+            hovered: None,
+            focus: None,
+        }
+    }
+}
+
+impl Ui {
+    pub fn prepare(&mut self, game: &mut Game, scene: &mut Scene<Texture>) {
         let back = {
-            self.graphics_env.as_ref().unwrap().get_texture(TextureKind::BACK)
-        };
-        let mut ge = {
-            self.graphics_env.as_mut().unwrap()
+            self.graphics_env.as_ref().unwrap().back()
         };
 
-        for p in &mut self.players {
-            let mut i = 0;
+        for p in &mut game.players {
             for c in &mut p.hand.iter() {
-                let mut sprite = Sprite::from_texture(back.clone());
-                c.sprite = Some(SpriteRef::new(sprite.id(), SpriteInfo{
-                    card: c.to_owned()
-                }));
-                scene.add_child(sprite);
-                i = i + 1;
+                c.ensure_sprite(scene, back.clone());
             }
         }
 
-        let mut i = 0;
-        for p in &mut self.table.into_iter() {
+        for p in &mut game.table {
             for c in &mut p.iter() {
-                let mut sprite = Sprite::from_texture(back.clone());
-                c.sprite = Some(SpriteRef::new(sprite.id(), SpriteInfo{
-                    card: c.to_owned()
-                }));
-                scene.add_child(sprite);
-                i = i + 1;
+                c.ensure_sprite(scene, back.clone());
             }
         }
     }
 
-    pub fn update(&mut self, size: &Size, scene: &mut Scene<Texture>) {
-        let (x, y) = (size.width, size.height);
+    pub fn update(&mut self, game: &mut Game, size: &Size, scene: &mut Scene<Texture>) {
+        // the cards go below the text, which takes a line for each player, four more at most and
+        // the lines of the message, keeping room for one when there is none
+        let message = self.message.as_ref().map_or(0, |m| m.lines().count()).max(1);
+        let top = LINE_HEIGHT * (game.players.len() + 4 + message) as f64 + 10.0;
+        let layout = Layout::new(size.width, size.height, top, game.players.len());
+        let piles: Vec<usize> = game.table.piles().iter().map(|p| p.len()).collect();
+        let table_positions = layout.table(&piles);
+        // hands are shown as the seat at the window sees them
+        let seen = match self.viewer {
+            Viewer::InTurn if self.screened(game) => vec!(),
+            Viewer::InTurn => game.player_turn.map(|seat| game.view(seat).hand).unwrap_or_default(),
+            Viewer::Seat(seat) => game.view(seat).hand,
+            Viewer::Spectator => vec!(),
+        };
 
+        // faces drawn as SVG are rasterized the size the cards are shown at
+        self.graphics_env.as_mut().unwrap().fit_faces(layout.card_size().0);
         let ge = {
             self.graphics_env.as_ref().unwrap()
         };
         let players = {
-            &mut self.players
+            &mut game.players
         };
         let table = {
-            &mut self.table
+            &mut game.table
         };
-        let deck = {
-            &self.deck
+        let time = self.animation_time;
+        let dragged = self.drag.as_ref().map(|d| d.sprite);
+        let place = |scene: &mut Scene<Texture>, c: &Card, position: [f64; 2], turned: bool| {
+            let sprite_ref = c.sprite.unwrap();
+            // the dragged card stays with the pointer
+            if Some(sprite_ref) == dragged {
+                return;
+            }
+            place(scene, sprite_ref.0, position, layout.deck(), layout.scale, turned, time);
         };
 
-        let mut pnum = 0;
-        for p in players {
-            let pos = (pnum as f64) *800.0 + 270.0;
-            let mut i = 0;
-            for c in &mut p.hand.iter() {
-                if self.player_turn.is_some() && self.player_turn.unwrap() == pnum {
-                    c.front(scene, ge.get_texture(TextureKind::CARDS));
-                }
-                {
-                    let sprite = scene.child_mut(c.sprite.unwrap().0).unwrap();
-                    i = i + 1;
-                    sprite.set_position((i as f64 * 200 as f64) + (x - 200.0), pos);
-                    sprite.set_rotation(0.0);
-                    for i in &self.interactions {
-                        match i {
-                            PlayerInteraction::Click(sprite_ref) => {
-                                if sprite_ref == &SpriteRef::from(&sprite.id()) {
-                                    sprite.set_rotation(20.0);
-                                }
-                            },
-                            _ => {}
-                        }
-                    }
-                }
+        for (pnum, p) in players.iter_mut().enumerate() {
+            let hand_positions = layout.hand(pnum, p.hand.len());
+            for (i, c) in (&mut p.hand.iter()).into_iter().enumerate() {
+                c.ensure_sprite(scene, ge.back());
+                let turned = if (self.reveal_all || seen.contains(c)) && !c.is_hidden() {
+                    c.front(scene, ge.face(c), &ge.back)
+                } else {
+                    c.back(scene, ge.back())
+                };
+                place(scene, c, hand_positions[i], turned);
+            }
+            for c in &mut p.score.iter() {
+                c.ensure_sprite(scene, ge.back());
+                let turned = c.back(scene, ge.back());
+                place(scene, c, layout.score(pnum), turned);
             }
-            pnum = pnum+1;
         }
 
-
-        let mut i = 0;
-        for p in &mut table.into_iter() {
-            for c in &mut p.iter() {
-                c.front(scene, ge.get_texture(TextureKind::CARDS));
-                let sprite = scene.child_mut(c.sprite.unwrap().0).unwrap();
-                i = i + 1;
-                sprite.set_position((i as f64 *200 as f64)+(x-200.0), 400.0+270.0);
-                sprite.set_rotation(0.0);
-                for i in &self.interactions {
-                    match i {
-                        PlayerInteraction::Click(sprite_ref) => {
-                            if sprite_ref == &SpriteRef::from(&sprite.id()) {
-                                sprite.set_rotation(20.0);
-                            }
-                        },
-                        _ => {}
-                    }
-                }
+        for (i, p) in table.into_iter().enumerate() {
+            for (j, c) in (&mut p.iter()).into_iter().enumerate() {
+                c.ensure_sprite(scene, ge.back());
+                let turned = c.front(scene, ge.face(c), &ge.back);
+                place(scene, c, table_positions[i][j], turned);
             }
         }
     }
 
-    pub fn render(&mut self, scene: &mut Scene<Texture>, args: &RenderArgs) {
-        let gl = &mut self.graphics_env.as_mut().unwrap().gl;
-
+    pub fn render(&mut self, game: &Game, scene: &mut Scene<Texture>, args: &RenderArgs) {
+        let screened = self.screened(game);
+        let hud = self.hud(game);
+        let highlights = if screened { vec!() } else { self.highlights(game) };
+        // builds are labelled with their value and owner, below their last card
+        let labels: Vec<(SpriteRef, String)> = game.builds.iter().filter_map(|b| {
+            let card = b.pile.cards().pop()?;
+            Some((card.sprite?, format!("{} for {}", b.value, game.players[b.owner as usize].name)))
+        }).collect();
+        let summary = game.summary();
+        let frame = game.summary_frame(args.window_size[0], args.window_size[1]);
+        let button = game.summary_button_label();
+        let GraphicsEnv { gl, glyphs, table, .. } = self.graphics_env.as_mut().unwrap();
         gl.draw(args.viewport(), |c, g| {
             use graphics::*;
 
             // Clear the screen.
-            clear([0.3, 0.6, 0.3, 0.0], g);
+            clear(*table, g);
 
             let transform = c
                 .transform;
 
             scene.draw(transform, g);
+
+            for (sprite_ref, highlight) in &highlights {
+                let sprite = match scene.child(sprite_ref.0) {
+                    Some(sprite) if sprite.get_visible() => sprite,
+                    _ => continue
+                };
+                let (width, height) = hit::size(sprite);
+                let ((x, y), (scale_x, scale_y)) = (sprite.get_position(), sprite.get_scale());
+                let (width, height) = (width * scale_x, height * scale_y);
+                let (color, pad) = highlight.frame();
+                Rectangle::new_border(color, 2.0).draw(
+                    [-width / 2.0 - pad, -height / 2.0 - pad, width + 2.0 * pad, height + 2.0 * pad],
+                    &c.draw_state, transform.trans(x, y).rot_deg(sprite.get_rotation()), g);
+            }
+            for (sprite_ref, label) in &labels {
+                if let Some(sprite) = scene.child(sprite_ref.0) {
+                    let [x, y, _, height] = sprite.bounding_box();
+                    let _ = text([1.0, 0.5, 0.0, 1.0], 16, label, glyphs, transform.trans(x, y + height + 16.0), g);
+                }
+            }
+
+            // dim the table while the next player is asked to take the window
+            if screened {
+                rectangle([0.0, 0.0, 0.0, 0.8], [0.0, 0.0, args.window_size[0], args.window_size[1]], transform, g);
+            }
+
+            for (i, line) in hud.iter().enumerate() {
+                let position = transform.trans(10.0, LINE_HEIGHT * (i + 1) as f64);
+                let _ = text([1.0, 1.0, 1.0, 1.0], 18, line, glyphs, position, g);
+            }
+
+            // the summary of a deal that is over goes over the table
+            if let Some(frame) = frame {
+                let [x, y, width, _] = frame.panel;
+                rectangle([0.0, 0.0, 0.0, 0.85], frame.panel, transform, g);
+                let size = (frame.row_height * 0.75) as u32;
+                for (i, [label, detail, points]) in summary.iter().enumerate() {
+                    let baseline = y + 20.0 + frame.row_height * (i + 1) as f64;
+                    let color = if label.starts_with(' ') { [1.0, 1.0, 1.0, 1.0] } else { [1.0, 0.85, 0.0, 1.0] };
+                    let _ = text(color, size, label, glyphs, transform.trans(x + 20.0, baseline), g);
+                    let _ = text(color, size, detail, glyphs, transform.trans(x + 180.0, baseline), g);
+                    let _ = text(color, size, points, glyphs, transform.trans(x + width - 60.0, baseline), g);
+                }
+                rectangle([0.2, 0.4, 0.8, 1.0], frame.button, transform, g);
+                let [bx, by, _, bh] = frame.button;
+                let _ = text([1.0, 1.0, 1.0, 1.0], 18, button, glyphs, transform.trans(bx + 12.0, by + bh / 2.0 + 6.0), g);
+            }
         });
     }
 
-    pub fn keyboard(&mut self, key: Key) {
-        self.interactions.push(PlayerInteraction::Keyboard(key));
-        match self.try_to_intent() {
-            Ok(()) => {},
-            Err(IntentError::PartialIntent(msg)) => {
-                println!("{}", msg);
+    /// The cards to mark, in the order the frames are drawn: builds, then the piles the selected
+    /// hand card can play on, then the selected cards, then the cards under the pointer and the
+    /// keyboard cursor.
+    pub fn highlights(&self, game: &Game) -> Vec<(SpriteRef, Highlight)> {
+        let mut highlights = vec!();
+        for (pile, highlight) in self.pile_highlights(game).into_iter().enumerate() {
+            if let (Some(highlight), Some(deck)) = (highlight, game.table.get(pile)) {
+                highlights.extend(deck.cards().iter().filter_map(|c| c.sprite).map(|s| (s, highlight)));
+            }
+        }
+        for i in &self.interactions {
+            if let PlayerInteraction::Click(sprite_ref) = i {
+                highlights.push((*sprite_ref, Highlight::Selected));
+            }
+        }
+        highlights.extend(self.hovered.map(|s| (s, Highlight::Hovered)));
+        if self.may_play(game) && !self.screened(game) {
+            highlights.extend(self.focused_sprite(game).map(|s| (s, Highlight::Focused)));
+        }
+        highlights
+    }
+
+    /// How each pile on the table is marked: as a pile the selected hand card can take or build
+    /// on, as the legal moves tell, or else as a build.
+    pub fn pile_highlights(&self, game: &Game) -> Vec<Option<Highlight>> {
+        let hand = game.current_player().map(|p| p.hand);
+        let selected = self.interactions.iter().find_map(|i| match i {
+            PlayerInteraction::Click(sprite_ref) => Some(sprite_ref.get_info().card),
+            _ => None
+        }).filter(|card| hand.is_some_and(|h| h.contains(card)));
+        let intents = match &selected {
+            Some(_) if self.may_play(game) => game.legal_intents(),
+            _ => vec!(),
+        };
+        (0..game.table.len()).map(|pile| {
+            let playable = |build: bool| intents.iter().any(|intent| match intent {
+                Intent::Take(card, piles) => !build && Some(card) == selected.as_ref() && piles.contains(&pile),
+                Intent::Build(card, piles, _) => build && Some(card) == selected.as_ref() && piles.contains(&pile),
+                Intent::Trail(_) => false,
+            });
+            if playable(false) {
+                Some(Highlight::Takes)
+            } else if playable(true) {
+                Some(Highlight::BuildsOn)
+            } else if game.table.get(pile).is_some_and(|deck| game.builds.iter().any(|b| b.pile == deck)) {
+                Some(Highlight::Build)
+            } else {
+                None
+            }
+        }).collect()
+    }
+
+    /// The lines of text shown over the table: the state of the game, whose turn it is, what has
+    /// been selected for the next play, and what the window has to say about it.
+    pub fn hud(&self, game: &Game) -> Vec<String> {
+        let mut lines = vec!(format!("round {}{}, {} cards in the deck",
+                                     game.round, if game.last_round { " (last)" } else { "" }, game.deck.len()));
+        let standings = game.standings();
+        for (p, standing) in game.players.iter().zip(standings) {
+            let mut line = format!("{}: {} cards captured, {} sweeps", p.name, p.score.len(), p.sweeps);
+            if game.target > 0 {
+                line += &format!(", {} of {} points", standing, game.target);
+            }
+            lines.push(line);
+        }
+        lines.push(match game.player_turn {
+            Some(seat) if self.screened(game) => format!("{}, click to reveal your hand", game.players[seat as usize].name),
+            Some(seat) => format!("{} to play", game.players[seat as usize].name),
+            None if game.deal_pending() => "the deal is over, press enter to deal the next one".to_string(),
+            None => match game.match_winner() {
+                Some(winner) => format!("the game is over, {} wins", game.players[winner as usize].name),
+                None => "the game is over".to_string(),
             },
-            Err(IntentError::IllegalAction(msg)) => {
-                println!("{}", msg);
+        });
+        let selected: Vec<String> = self.interactions.iter().filter_map(|i| match i {
+            PlayerInteraction::Click(sprite_ref) => Some(sprite_ref.get_info().card.to_string()),
+            _ => None
+        }).collect();
+        if !selected.is_empty() {
+            lines.push(format!("selected {}", selected.join(" ")));
+        }
+        if self.may_play(game) && !self.screened(game) {
+            lines.push(keyboard::HINTS.to_string());
+        }
+        lines.extend(self.message.iter().flat_map(|m| m.lines()).map(String::from));
+        lines
+    }
+
+    /// Replaces the cards of `game` wholesale with `change`, e.g. to mirror a game played
+    /// elsewhere. Cards still in the game keep their sprites, so they move rather than appear
+    /// anew, and the sprites of the others are taken off `scene`.
+    pub fn replace_cards<F: FnOnce(&mut Game)>(&mut self, game: &mut Game, scene: &mut Scene<Texture>, change: F) {
+        let mut sprites = vec!();
+        for mut deck in game.decks() {
+            for c in &mut deck.iter() {
+                if let Some(sprite_ref) = c.sprite.take() {
+                    sprites.push((c.clone(), sprite_ref));
+                }
             }
-            Err(_) => {}
+        }
+        change(game);
+        for mut deck in game.decks() {
+            for c in &mut deck.iter() {
+                if let Some(i) = sprites.iter().position(|(card, _)| card == c) {
+                    let sprite_ref = sprites.remove(i).1;
+                    c.sprite = Some(sprite_ref);
+                    SPRITES.write().unwrap().insert(sprite_ref, SpriteInfo {
+                        card: c.to_owned()
+                    });
+                }
+            }
+        }
+        for (_, sprite_ref) in sprites {
+            scene.stop_all(sprite_ref.0);
+            scene.remove_child(sprite_ref.0);
+            SPRITES.write().unwrap().remove(&sprite_ref);
+        }
+        self.interactions.clear();
+    }
+
+    /// Whether the window waits for the player in turn to reveal their hand before they play.
+    pub fn screened(&self, game: &Game) -> bool {
+        let human = |seat: u8| game.players[seat as usize].controller.is_none();
+        self.privacy_screen && self.revealed != game.player_turn && self.viewer == Viewer::InTurn && game.player_turn.is_some_and(human)
+    }
+
+    /// Whether the window may make plays now: someone is in turn, it is the window's seat, and
+    /// the computer does not play it.
+    pub(crate) fn may_play(&self, game: &Game) -> bool {
+        if game.current_player().is_some_and(|p| p.controller.is_some()) {
+            return false;
+        }
+        match self.viewer {
+            Viewer::InTurn => game.player_turn.is_some(),
+            Viewer::Seat(seat) => game.player_turn == Some(seat),
+            Viewer::Spectator => false,
+        }
+    }
+
+    /// Picks up the card under the pointer to drag it, if it is in the hand of the player in
+    /// turn. Other presses are clicks.
+    pub fn mouse_down(&mut self, game: &mut Game, scene: &mut Scene<Texture>, position: [f64; 2]) {
+        let sprite_ref = self.card_at(game, scene, position, None);
+        let in_hand = |s: &SpriteRef| game.current_player().is_some_and(|p| p.hand.contains(&s.get_info().card));
+        match sprite_ref.filter(in_hand) {
+            Some(sprite_ref) => {
+                // the dragged card is drawn over the others
+                scene.stop_all(sprite_ref.0);
+                let sprite = scene.remove_child(sprite_ref.0).unwrap();
+                let (x, y) = sprite.get_position();
+                scene.add_child(sprite);
+                self.drag = Some(Drag {
+                    sprite: sprite_ref,
+                    grab: [position[0] - x, position[1] - y],
+                    start: position,
+                    moved: false,
+                });
+            },
+            _ => self.click(game, sprite_ref),
         }
     }
 
-    pub fn click(&mut self, sprite_ref: Option<SpriteRef>) {
-        if self.player_turn.is_some() {
+    /// Moves the dragged card with the pointer, and highlights the pile it would be dropped on.
+    /// Otherwise notes the card the pointer is over.
+    pub fn mouse_moved(&mut self, game: &Game, scene: &mut Scene<Texture>, position: [f64; 2]) {
+        if self.drag.is_none() {
+            self.hovered = self.card_at(game, scene, position, None);
+        }
+        let (sprite_ref, grab) = match self.drag.as_mut() {
+            Some(drag) => {
+                drag.moved |= (position[0] - drag.start[0]).hypot(position[1] - drag.start[1]) > 5.0;
+                if !drag.moved {
+                    return;
+                }
+                (drag.sprite, drag.grab)
+            },
+            None => return
+        };
+        scene.child_mut(sprite_ref.0).unwrap().set_position(position[0] - grab[0], position[1] - grab[1]);
+        let target = self.drop_target(game, scene, position);
+        for (i, pile) in game.table.piles().iter().enumerate() {
+            let (r, g, b) = if Some(i) == target { DROP_TINT } else { (1.0, 1.0, 1.0) };
+            for c in pile.cards() {
+                if let Some(sprite) = c.sprite.and_then(|s| scene.child_mut(s.0)) {
+                    sprite.set_color(r, g, b);
+                }
+            }
+        }
+    }
+
+    /// Drops the dragged card where the pointer is, as `drop_intent` tells. A card that cannot go
+    /// there goes back to the hand when the table is laid out again, and one let go of where it
+    /// was picked up is clicked.
+    pub fn mouse_up(&mut self, game: &mut Game, scene: &mut Scene<Texture>, position: [f64; 2]) {
+        let drag = match self.drag.take() {
+            Some(drag) => drag,
+            None => return
+        };
+        if !drag.moved {
+            self.click(game, Some(drag.sprite));
+            return;
+        }
+        let target = self.drop_target(game, scene, position);
+        match game.drop_intent(&self.interactions, drag.sprite.get_info().card, target) {
+            Ok(intent) => self.submit(game, intent),
+            Err(IntentError::IllegalAction(msg)) | Err(IntentError::PartialIntent(msg)) => {
+                self.message = Some(msg.to_string());
+            },
+            Err(IntentError::Unknown) => {}
+        }
+    }
+
+    /// The pile on the table under `position`, other than the dragged card.
+    fn drop_target(&self, game: &Game, scene: &Scene<Texture>, position: [f64; 2]) -> Option<usize> {
+        let dragged = self.drag.as_ref().map(|d| d.sprite);
+        self.card_at(game, scene, position, dragged).and_then(|s| game.table.pile_of(&s.get_info().card))
+    }
+
+    pub fn click(&mut self, game: &mut Game, sprite_ref: Option<SpriteRef>) {
+        if self.screened(game) {
+            self.revealed = game.player_turn;
+        } else if self.may_play(game) {
             match sprite_ref {
                 Some(sr) => {
                     self.interactions.push(PlayerInteraction::Click(sr));
-                    match self.try_to_intent() {
-                        Ok(()) => {},
-                        Err(IntentError::PartialIntent(msg)) => {
-                            println!("{}", msg);
-                        },
-                        Err(IntentError::IllegalAction(msg)) => {
-                            println!("{}", msg);
-                        }
-                        Err(_) => {}
-                    }
+                    self.interact(game);
                 },
-                None => self.interactions.clear()
+                None => {
+                    self.interactions.clear();
+                    self.message = None;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn interact(&mut self, game: &mut Game) {
+        match game.try_to_intent(&self.interactions) {
+            Ok(intent) => self.submit(game, intent),
+            Err(IntentError::PartialIntent(msg)) => {
+                self.message = Some(msg.to_string());
+            },
+            Err(IntentError::IllegalAction(msg)) => {
+                self.message = Some(msg.to_string());
+                self.interactions.clear();
             }
+            Err(_) => {}
+        }
+    }
+
+    /// Plays `intent`, or sends it to the server that owns the game.
+    fn submit(&mut self, game: &mut Game, intent: Intent) {
+        self.interactions.clear();
+        self.message = None;
+        if let Some(submit) = &self.submit {
+            let _ = submit.send(intent);
+        } else if let Err(IntentError::IllegalAction(msg)) = game.play(intent) {
+            self.message = Some(msg.to_string());
         }
     }
 }
 
+impl Game {
+    fn decks(&self) -> Vec<DeckRef> {
+        let mut decks = vec!(self.deck);
+        for p in &self.players {
+            decks.push(p.hand);
+            decks.push(p.score);
+        }
+        decks.extend_from_slice(self.table.piles());
+        decks
+    }
+}
+
+/// Moves a card to `position`, sliding it there and turning it over when its face has changed,
+/// unless animations are off. Cards that have not been placed yet come from the deck.
+fn place(scene: &mut Scene<Texture>, id: Uuid, position: [f64; 2], deck: [f64; 2], scale: f64, turned: bool, time: f64) {
+    let sprite = scene.child_mut(id).unwrap();
+    // art of any size is drawn the size of a card in the layout
+    let (width, height) = hit::size(sprite);
+    let scale = (scale * CARD_WIDTH / width, scale * CARD_HEIGHT / height);
+    sprite.set_scale(scale.0, scale.1);
+    sprite.set_color(1.0, 1.0, 1.0);
+    sprite.set_rotation(0.0);
+    if time <= 0.0 {
+        sprite.set_position(position[0], position[1]);
+        sprite.set_visible(true);
+        return;
+    }
+    if !sprite.get_visible() {
+        sprite.set_position(deck[0], deck[1]);
+        sprite.set_visible(true);
+    }
+    let (x, y) = sprite.get_position();
+    let moved = (x - position[0]).abs() > 0.5 || (y - position[1]).abs() > 0.5;
+    if turned {
+        sprite.set_scale(0.0, scale.1);
+    }
+    if moved {
+        scene.stop_all(id);
+        scene.run(id, &Action(Ease(EaseFunction::CubicOut, Box::new(MoveTo(time, position[0], position[1])))));
+    }
+    if turned {
+        scene.run(id, &Action(ScaleTo(time / 2.0, scale.0, scale.1)));
+    }
+}
+
 impl Card {
-    fn front(&mut self, scene: &mut Scene<Texture>, texture: Rc<Texture>) {
+    fn ensure_sprite(&mut self, scene: &mut Scene<Texture>, texture: Rc<Texture>) {
+        if self.sprite.is_none() {
+            let mut sprite = Sprite::from_texture(texture);
+            // shown once it has been placed
+            sprite.set_visible(false);
+            self.sprite = Some(SpriteRef::new(sprite.id(), SpriteInfo{
+                card: self.to_owned()
+            }));
+            scene.add_child(sprite);
+        }
+    }
+
+    /// Shows the face of the card, and tells whether it was turned over from `back` to do so.
+    /// Faces drawn again at another size are not turned.
+    fn front(&mut self, scene: &mut Scene<Texture>, (texture, rect): (Rc<Texture>, Option<[f64; 4]>), back: &Rc<Texture>) -> bool {
         if let Some(sprite_ref) = self.sprite {
-            let offset = 15.0;
-            let width = 180.0;
-            let height = 270.0;
-
-            let row = match self.suit {
-                Suit::SPADES => 1.0,
-                Suit::HEARTS => 2.0,
-                Suit::DIAMONDS => 3.0,
-                Suit::CLUBS => 4.0,
-            };
-            let col = self.value as f64;
-            let corners = (offset*col+width*(col-1.0), offset*row+height*(row-1.0), offset*col+width*col, offset*row+height*row);
             let sprite = scene.child_mut(sprite_ref.0).unwrap();
+            let turned = Rc::ptr_eq(sprite.get_texture(), back);
+            let (width, height) = texture.get_size();
             sprite.set_texture(texture);
-            sprite.set_src_rect(rectangle_by_corners(corners.0,
-                                                     corners.1,
-                                                     corners.2,
-                                                     corners.3))
+            sprite.set_src_rect(rect.unwrap_or([0.0, 0.0, width as f64, height as f64]));
+            return turned;
         }
+        false
     }
-    fn back(&mut self, scene: &mut Scene<Texture>, texture: Rc<Texture>) {
+
+    /// Shows the back of the card, and tells whether it was turned over to do so.
+    fn back(&mut self, scene: &mut Scene<Texture>, texture: Rc<Texture>) -> bool {
         if let Some(sprite_ref) = self.sprite {
             let sprite = scene.child_mut(sprite_ref.0).unwrap();
+            let turned = !Rc::ptr_eq(sprite.get_texture(), &texture);
+            let (width, height) = texture.get_size();
             sprite.set_texture(texture);
+            sprite.set_src_rect([0.0, 0.0, width as f64, height as f64]);
+            return turned;
         }
+        false
     }
 }
 
 fn load_texture(path: &Path) -> Rc<Texture> {
     Rc::new(Texture::from_path(
-        &path,
+        path,
         &TextureSettings::new()
     ).unwrap())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Intent, Player};
+
+    #[test]
+    fn test_hud_follows_the_game() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.start();
+        let mut ui = Ui::default();
+        assert_eq!(ui.hud(&game), vec!(
+            "round 1, 40 cards in the deck",
+            "player1: 0 cards captured, 0 sweeps",
+            "player2: 0 cards captured, 0 sweeps",
+            "player1 to play",
+            keyboard::HINTS,
+        ));
+
+        let card = game.players[0].hand.cards()[0].clone();
+        game.play(Intent::Trail(card)).unwrap();
+        ui.privacy_screen = true;
+        ui.message = Some("choose a card from your hand".to_string());
+        let hud = ui.hud(&game);
+        assert_eq!(hud[3], "player2, click to reveal your hand");
+        assert_eq!(hud[4], "choose a card from your hand");
+        ui.message = Some("seat 1 has lost its connection\nseat 1 is back".to_string());
+        assert_eq!(ui.hud(&game)[4..], ["seat 1 has lost its connection", "seat 1 is back"]);
+
+        // the window keeps its hands off the computer's cards
+        assert!(ui.may_play(&game));
+        game.players[1].controller = Some(crate::ai::from_spec("easy").unwrap());
+        assert!(!ui.may_play(&game));
+    }
+
+    #[test]
+    fn test_turn_change_covers_hands() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        let mut ui = Ui {
+            privacy_screen: true,
+            ..Default::default()
+        };
+        game.start();
+        assert!(ui.screened(&game));
+        ui.click(&mut game, None);
+        assert!(!ui.screened(&game));
+
+        let card = game.players[0].hand.cards()[0].clone();
+        game.play(Intent::Trail(card)).unwrap();
+        assert!(ui.screened(&game));
+    }
+
+    #[test]
+    fn test_piles_the_selected_card_can_play_on() {
+        let card = |s: &str| s.parse::<Card>().unwrap();
+        let mut player = Player::new(0, "player1");
+        player.deal(&mut vec!(card("H:2"), card("C:7"), card("S:9")));
+        let mut game = Game {
+            players: vec!(player, Player::new(1, "player2")),
+            player_turn: Some(0),
+            ..Default::default()
+        };
+        game.table.new_pile(vec!(card("C:5")));
+        game.table.new_pile(vec!(card("D:9")));
+        let mut ui = Ui::default();
+        assert_eq!(ui.pile_highlights(&game), vec!(None, None));
+
+        let select = |ui: &mut Ui, c: &str| {
+            let sprite_ref = SpriteRef::new(Uuid::new(uuid::UuidVersion::Random).unwrap(), SpriteInfo { card: card(c) });
+            ui.interactions = vec!(PlayerInteraction::Click(sprite_ref));
+        };
+        select(&mut ui, "H:2");
+        assert_eq!(ui.pile_highlights(&game), vec!(Some(Highlight::BuildsOn), None));
+        select(&mut ui, "S:9");
+        assert_eq!(ui.pile_highlights(&game), vec!(None, Some(Highlight::Takes)));
+
+        ui.interactions.clear();
+        game.play(Intent::Build(card("H:2"), vec!(0), 7)).unwrap();
+        assert_eq!(ui.pile_highlights(&game), vec!(None, Some(Highlight::Build)));
+    }
+}
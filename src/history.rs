@@ -0,0 +1,230 @@
+use crate::types::{Game, Player, Card, Build, Deck, DeckRef, Table, LogEntry};
+use crate::kasino::IntentError;
+use crate::events::Event;
+
+/// The state of a game before a play: the contents of every deck it owns, in order, and the
+/// bookkeeping that the play may change.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    decks: Vec<(DeckRef, Vec<Card>)>,
+    table: Table,
+    builds: Vec<Build>,
+    sweeps: Vec<u8>,
+    last_round: bool,
+    last_capture: Option<u8>,
+    round: u8,
+    player_turn: Option<u8>,
+    log_len: usize,
+}
+
+impl Game {
+    pub fn snapshot(&self) -> Snapshot {
+        let mut decks = vec!((self.deck, self.deck.cards()));
+        for p in &self.players {
+            decks.push((p.hand, p.hand.cards()));
+            decks.push((p.score, p.score.cards()));
+        }
+        for pile in self.table.piles() {
+            decks.push((*pile, pile.cards()));
+        }
+        Snapshot {
+            decks,
+            table: self.table.clone(),
+            builds: self.builds.clone(),
+            sweeps: self.players.iter().map(|p| p.sweeps).collect(),
+            last_round: self.last_round,
+            last_capture: self.last_capture,
+            round: self.round,
+            player_turn: self.player_turn,
+            log_len: self.log.len(),
+        }
+    }
+
+    /// Puts every deck back as it was when the snapshot was taken. Piles that were removed from
+    /// the table since are still registered, so they are restored along with the others; piles
+    /// that were put on the table since are released, as nothing refers to them any more.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        for pile in self.table.piles() {
+            if !snapshot.table.piles().contains(pile) {
+                Deck::release(*pile);
+            }
+        }
+        for (mut deck, cards) in snapshot.decks {
+            deck.set_cards(cards);
+        }
+        self.table = snapshot.table;
+        self.builds = snapshot.builds;
+        for (p, sweeps) in self.players.iter_mut().zip(snapshot.sweeps) {
+            p.sweeps = sweeps;
+        }
+        self.last_round = snapshot.last_round;
+        self.last_capture = snapshot.last_capture;
+        self.round = snapshot.round;
+        self.player_turn = snapshot.player_turn;
+        self.log.truncate(snapshot.log_len);
+    }
+
+    /// A headless copy of the game with decks of its own, for trying out plays. The copy keeps no
+    /// history and has no observers or controllers.
+    pub fn fork(&self) -> Game {
+        let copy = |deck: &DeckRef| Deck::new(deck.cards());
+        let piles: Vec<DeckRef> = self.table.piles().iter().map(copy).collect();
+        let builds = self.builds.iter().map(|b| {
+            let pile = self.table.piles().iter().position(|p| *p == b.pile).unwrap();
+            Build {
+                pile: piles[pile],
+                ..b.clone()
+            }
+        }).collect();
+
+        Game {
+            players: self.players.iter().map(|p| Player {
+                id: p.id,
+                name: p.name.clone(),
+                hand: copy(&p.hand),
+                score: copy(&p.score),
+                sweeps: p.sweeps,
+                controller: None,
+            }).collect(),
+            deck: copy(&self.deck),
+            table: Table::new(piles),
+            builds,
+            last_round: self.last_round,
+            last_capture: self.last_capture,
+            round: self.round,
+            player_turn: self.player_turn,
+            initial_deck: vec!(),
+            log: vec!(),
+            undo_enabled: false,
+            history: vec!(),
+            redo: vec!(),
+            observers: vec!(),
+            variant: self.variant,
+            target: self.target,
+            deals: self.deals.clone(),
+            seed: self.seed,
+        }
+    }
+
+    /// Unregisters the decks of a game that is no longer needed, such as a fork.
+    pub fn discard(self) {
+        Deck::release(self.deck);
+        for p in &self.players {
+            Deck::release(p.hand);
+            Deck::release(p.score);
+        }
+        for pile in self.table.piles() {
+            Deck::release(*pile);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.undo_enabled && !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.undo_enabled && !self.redo.is_empty()
+    }
+
+    /// Takes back the last play. The plays of seats the computer plays are taken back along with
+    /// it, back to the last play made at the window, as the computer would only play them again.
+    pub fn undo(&mut self) -> Result<(), IntentError> {
+        if !self.undo_enabled {
+            return Err(IntentError::IllegalAction("undo is disabled in this game"));
+        }
+        self.take_back()?;
+        let at_window = |game: &Game| game.player_turn.is_none_or(|seat| game.players[seat as usize].controller.is_none());
+        let anyone_at_window = self.players.iter().any(|p| p.controller.is_none());
+        while anyone_at_window && !at_window(self) && !self.history.is_empty() {
+            self.take_back()?;
+        }
+        Ok(())
+    }
+
+    fn take_back(&mut self) -> Result<(), IntentError> {
+        let snapshot = self.history.pop().ok_or(IntentError::IllegalAction("nothing to undo"))?;
+        let intent = self.log[snapshot.log_len..].iter().find_map(|entry| match entry {
+            LogEntry::Played(_, intent) => Some(intent.clone()),
+            _ => None
+        });
+        self.restore(snapshot);
+        if let Some(intent) = intent {
+            self.redo.push(intent.clone());
+            self.emit(Event::Undone(intent));
+            self.emit(Event::TurnChanged(self.player_turn));
+        }
+        Ok(())
+    }
+
+    /// Plays the last undone play again.
+    pub fn redo(&mut self) -> Result<(), IntentError> {
+        if !self.undo_enabled {
+            return Err(IntentError::IllegalAction("undo is disabled in this game"));
+        }
+        let intent = self.redo.pop().ok_or(IntentError::IllegalAction("nothing to redo"))?;
+        self.validate(&intent)?;
+        self.commit(intent);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{Game, Player, Intent, Deck};
+
+    #[test]
+    fn test_fork_is_independent() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.start();
+        let hand = game.players[0].hand.cards();
+        let mut fork = game.fork();
+        fork.play(Intent::Trail(hand[0].clone())).unwrap();
+        assert_eq!(fork.table.len(), 5);
+        assert_eq!(game.table.len(), 4);
+        assert_eq!(game.players[0].hand.cards(), hand);
+        assert!(fork.history.is_empty());
+        fork.discard();
+    }
+
+    #[test]
+    fn test_undo_restores_decks_exactly() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.start();
+        let hand = game.players[0].hand.cards();
+        let table = game.table.piles().to_vec();
+        let deck = game.deck.cards();
+
+        game.play(Intent::Trail(hand[2].clone())).unwrap();
+        assert_eq!(game.player_turn, Some(1));
+        let trailed = *game.table.piles().iter().find(|p| !table.contains(p)).unwrap();
+        assert!(Deck::registered(trailed));
+        game.undo().unwrap();
+        assert_eq!(game.player_turn, Some(0));
+        assert_eq!(game.players[0].hand.cards(), hand);
+        assert_eq!(game.table.piles(), &table[..]);
+        assert_eq!(game.deck.cards(), deck);
+        assert!(game.undo().is_err());
+        // the pile of the trail is released along with it
+        assert!(!Deck::registered(trailed));
+
+        game.redo().unwrap();
+        assert_eq!(game.players[0].hand.len(), 3);
+        assert_eq!(game.table.len(), 5);
+
+        // the computer's play is taken back along with the play before it
+        game.players[1].controller = Some(crate::ai::from_spec("easy").unwrap());
+        assert!(game.advance());
+        game.undo().unwrap();
+        assert_eq!(game.player_turn, Some(0));
+        assert_eq!(game.players[0].hand.cards(), hand);
+
+        game.undo_enabled = false;
+        assert!(game.undo().is_err());
+    }
+}
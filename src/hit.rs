@@ -0,0 +1,106 @@
+//! Finds the card under the pointer. Cards are hit where they are drawn, rotated and scaled, and
+//! a card drawn over another hides it.
+
+use crate::graphic::{SpriteRef, Ui};
+use crate::types::Game;
+use graphics::ImageSize;
+use sprite::{Scene, Sprite};
+
+/// Whether `point` is on `sprite` as it is drawn.
+pub fn hit<I: ImageSize>(sprite: &Sprite<I>, point: [f64; 2]) -> bool {
+    if !sprite.get_visible() {
+        return false;
+    }
+    let (width, height) = size(sprite);
+    let (x, y) = sprite.get_position();
+    let (scale_x, scale_y) = sprite.get_scale();
+    if scale_x == 0.0 || scale_y == 0.0 {
+        return false;
+    }
+    // undo the translation, rotation and scale the sprite is drawn with
+    let (sin, cos) = (-sprite.get_rotation().to_radians()).sin_cos();
+    let (dx, dy) = (point[0] - x, point[1] - y);
+    let local_x = (dx * cos - dy * sin) / scale_x;
+    let local_y = (dx * sin + dy * cos) / scale_y;
+    let (anchor_x, anchor_y) = sprite.get_anchor();
+    let (left, top) = (-anchor_x * width, -anchor_y * height);
+    local_x >= left && local_x <= left + width && local_y >= top && local_y <= top + height
+}
+
+/// The size of `sprite` before it is scaled.
+pub fn size<I: ImageSize>(sprite: &Sprite<I>) -> (f64, f64) {
+    match sprite.get_src_rect() {
+        Some(rect) => (rect[2], rect[3]),
+        None => {
+            let (w, h) = sprite.get_texture().get_size();
+            (w as f64, h as f64)
+        }
+    }
+}
+
+/// The topmost sprite under `point`, other than `except`.
+pub fn topmost<I: ImageSize>(scene: &Scene<I>, point: [f64; 2], except: Option<SpriteRef>) -> Option<SpriteRef> {
+    scene.children().iter().rev()
+        .filter(|s| Some(SpriteRef::from(&s.id())) != except)
+        .find(|s| hit(s, point))
+        .map(|s| SpriteRef::from(&s.id()))
+}
+
+impl Ui {
+    /// Whether the window may do anything with the card of `sprite_ref`: it is on the table, or
+    /// in the hand of the player in turn, and the window may play for them.
+    pub fn interactive(&self, game: &Game, sprite_ref: SpriteRef) -> bool {
+        if self.screened(game) || !self.may_play(game) {
+            return false;
+        }
+        let card = sprite_ref.get_info().card;
+        game.table.contains(&card) || game.current_player().is_some_and(|p| p.hand.contains(&card))
+    }
+
+    /// The card under `point` that the window may do anything with. A card hidden under another
+    /// card is not hit.
+    pub fn card_at<I: ImageSize>(&self, game: &Game, scene: &Scene<I>, point: [f64; 2], except: Option<SpriteRef>) -> Option<SpriteRef> {
+        topmost(scene, point, except).filter(|s| self.interactive(game, *s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    struct Image;
+
+    impl ImageSize for Image {
+        fn get_size(&self) -> (u32, u32) {
+            (100, 200)
+        }
+    }
+
+    #[test]
+    fn test_hit_respects_rotation_and_order() {
+        let mut scene = Scene::new();
+        let mut below = Sprite::from_texture(Rc::new(Image));
+        below.set_position(100.0, 150.0);
+        let below = scene.add_child(below);
+        let mut above = Sprite::from_texture(Rc::new(Image));
+        above.set_position(150.0, 150.0);
+        above.set_scale(0.5, 0.5);
+        let above = scene.add_child(above);
+
+        // the sprite drawn last is hit where the sprites overlap
+        assert_eq!(topmost(&scene, [130.0, 150.0], None), Some(SpriteRef::from(&above)));
+        assert_eq!(topmost(&scene, [130.0, 150.0], Some(SpriteRef::from(&above))), Some(SpriteRef::from(&below)));
+        // the scaled sprite is 50 wide
+        assert_eq!(topmost(&scene, [60.0, 150.0], None), Some(SpriteRef::from(&below)));
+        assert_eq!(topmost(&scene, [145.0, 220.0], None), Some(SpriteRef::from(&below)));
+
+        // turned a quarter, the sprite is wider than it is tall
+        let sprite = scene.child_mut(below).unwrap();
+        sprite.set_rotation(90.0);
+        assert!(hit(sprite, [190.0, 150.0]));
+        assert!(!hit(sprite, [100.0, 240.0]));
+        sprite.set_visible(false);
+        assert!(!hit(sprite, [100.0, 150.0]));
+    }
+}
@@ -1,37 +1,42 @@
-use crate::types::{Game, Player, Deck, PlayerInteraction};
-use crate::graphic::GraphicsEnv;
+use crate::types::{Game, Player, Card, Suit, Build, Deck, DeckRef, Intent, LogEntry, PlayerInteraction, Table};
+use crate::events::Event;
 use std::default::Default;
-use piston::input::UpdateEvent;
-use piston_window::texture::ImageSize;
-use std::collections::HashMap;
-use uuid::Uuid;
+use std::fmt;
+use std::str::FromStr;
+use piston::Key;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 impl Game {
 
-    pub fn new(graphics_env: GraphicsEnv, players: Vec<Player>) -> Game {
+    pub fn new(players: Vec<Player>) -> Game {
         Game{
             players,
-            graphics_env: Some(graphics_env),
             ..Default::default()
         }
     }
 
     pub fn start(&mut self) {
+        self.initial_deck = self.deck.cards();
         self.setup();
     }
 
     fn setup(&mut self) {
-        self.deal_each_player(2);
-        self.deal_table(2);
-        self.deal_each_player(2);
-        self.deal_table(2);
+        let _ = self.deal_each_player(2);
+        let _ = self.deal_table(2);
+        let _ = self.deal_each_player(2);
+        let _ = self.deal_table(2);
+        self.round = 1;
         self.player_turn = Some(0);
+        self.emit(Event::TurnChanged(self.player_turn));
     }
 
     fn end_of_round(&mut self) {
+        self.emit(Event::RoundEnded(self.round));
         if !self.last_round {
-            self.deal_each_player(2);
-            self.deal_each_player(2);
+            let _ = self.deal_each_player(2);
+            let _ = self.deal_each_player(2);
+            self.round += 1;
             if self.deck.is_empty() {
                 self.last_round = true;
             }
@@ -40,14 +45,36 @@ impl Game {
         }
     }
 
-    fn end_of_game(&self) {
-        //TODO: count points etc.
+    fn end_of_game(&mut self) {
+        self.player_turn = None;
+        if let Some(number) = self.last_capture {
+            let mut cleared = vec!();
+            let mut score = self.players[number as usize].score;
+            while !self.table.is_empty() {
+                let mut pile = self.table.remove(0);
+                cleared.append(&mut pile.cards());
+                pile.transfer_all(&mut score);
+                self.release(pile);
+            }
+            self.builds.clear();
+            self.log.push(LogEntry::Cleared(number, cleared.clone()));
+            if !cleared.is_empty() {
+                self.emit(Event::TableCleared(number, cleared));
+            }
+        }
+        self.emit(Event::TurnChanged(None));
+        self.emit(Event::GameEnded);
     }
 
     fn deal_each_player(&mut self, count: usize) -> Result<(), KasinoError> {
         if self.deck.has_cards(count*self.players.len()) {
-            for p in &mut self.players {
-                p.deal(&mut self.deck.draw(count).ok_or(KasinoError::DeckOrPileEmpty)?);
+            for number in 0..self.players.len() {
+                let mut cards = self.deck.draw(count).ok_or(KasinoError::DeckOrPileEmpty)?;
+                self.log.push(LogEntry::Dealt(Some(number as u8), cards.clone()));
+                for card in &cards {
+                    self.emit(Event::CardDealt(Some(number as u8), card.clone()));
+                }
+                self.players[number].deal(&mut cards);
             }
             Ok(())
         } else {
@@ -58,7 +85,10 @@ impl Game {
     fn deal_table(&mut self, count: usize) -> Result<(), KasinoError> {
         if self.deck.has_cards(count) {
             for _ in 0..count {
-                self.table.new_pile(self.deck.draw(1).ok_or(KasinoError::DeckOrPileEmpty)?);
+                let cards = self.deck.draw(1).ok_or(KasinoError::DeckOrPileEmpty)?;
+                self.log.push(LogEntry::Dealt(None, cards.clone()));
+                self.emit(Event::CardDealt(None, cards[0].clone()));
+                self.table.new_pile(cards);
             }
             Ok(())
         } else {
@@ -66,67 +96,734 @@ impl Game {
         }
     }
 
-    fn current_player<'p>(&'p self) -> Option<&'p Player> {
+    pub fn current_player(&self) -> Option<&Player> {
         self.player_turn.and_then(|number| self.players.get(number as usize))
     }
 
-    pub fn try_to_intent(&self) -> Result<(), IntentError> {
-        match self.interactions.len() {
-            1 => {
-                self.interactions.first().as_ref().ok_or(IntentError::Unknown).and_then(|i| {
-                    match i {
-                        PlayerInteraction::Click(sprite_ref) => {
-                            if self.current_player().unwrap().hand.contains(&sprite_ref.get_info().card) {
-                                Err(IntentError::PartialIntent("(d) to drop"))
-                            } else {
-                                Ok(())
-                            }
-                        },
-                        _ => { Ok(()) }
+    /// The value a table pile counts for: the declared value of a build, otherwise the sum of its cards.
+    pub fn pile_value(&self, pile: usize) -> Option<u8> {
+        let deck = self.table.get(pile)?;
+        match self.builds.iter().find(|b| b.pile == deck) {
+            Some(build) => Some(build.value),
+            None => Some(deck.cards().iter().map(|c| c.table_value()).sum()),
+        }
+    }
+
+    fn is_build(&self, pile: usize) -> bool {
+        self.table.get(pile).map(|deck| self.builds.iter().any(|b| b.pile == deck)).unwrap_or(false)
+    }
+
+    /// Interprets the interactions collected so far as an intent of the player in turn.
+    pub fn try_to_intent(&self, interactions: &[PlayerInteraction]) -> Result<Intent, IntentError> {
+        let player = self.current_player().ok_or(IntentError::Unknown)?;
+        let mut card = None;
+        let mut piles = vec!();
+        let mut key = None;
+        for i in interactions {
+            match i {
+                PlayerInteraction::Click(sprite_ref) => {
+                    let clicked = sprite_ref.get_info().card;
+                    if player.hand.contains(&clicked) {
+                        if card.is_some() {
+                            return Err(IntentError::IllegalAction("only one card can be played at a time"));
+                        }
+                        card = Some(clicked);
+                    } else if let Some(pile) = self.table.pile_of(&clicked) {
+                        if !piles.contains(&pile) {
+                            piles.push(pile);
+                        }
+                    } else {
+                        return Err(IntentError::IllegalAction("that card is not in play"));
                     }
-                })
+                },
+                PlayerInteraction::Keyboard(k) => key = Some(*k),
+            }
+        }
+
+        let card = card.ok_or(IntentError::PartialIntent("select a card from your hand"))?;
+        match key {
+            Some(Key::T) | Some(Key::D) => Ok(Intent::Trail(card)),
+            Some(Key::C) => Ok(Intent::Take(card, piles)),
+            Some(Key::B) => {
+                let value = self.build_value(&card, &piles)?;
+                Ok(Intent::Build(card, piles, value))
             },
-            2 => {
-                self.interactions.last().as_ref().ok_or(IntentError::Unknown).and_then(|i| {
-                    match i {
-                        PlayerInteraction::Click(sprite_ref) => {
-                            if self.current_player().unwrap().hand.contains(&sprite_ref.get_info().card) {
-                                Err(IntentError::PartialIntent("(d) to drop"))
-                            } else {
-                                Ok(())
-                            }
-                        },
-                        _ => { Ok(()) }
-                    }
-                }) 
+            Some(_) => Err(IntentError::IllegalAction("unknown key")),
+            None if piles.is_empty() => Err(IntentError::PartialIntent("(t) to trail, or select piles on the table")),
+            None => {
+                let take = Intent::Take(card, piles);
+                match self.validate(&take) {
+                    Ok(()) => Ok(take),
+                    Err(_) => Err(IntentError::PartialIntent("(c) to capture, (b) to build")),
+                }
+            }
+        }
+    }
+
+    /// Interprets dropping `card` from the hand onto the pile `target`, or beside the piles when
+    /// there is no target. The target is taken, or built on if it cannot be taken, along with
+    /// any piles selected by `interactions` before; a card dropped beside the piles is trailed.
+    pub fn drop_intent(&self, interactions: &[PlayerInteraction], card: Card, target: Option<usize>) -> Result<Intent, IntentError> {
+        let target = match target {
+            Some(target) => target,
+            None => {
+                let trail = Intent::Trail(card);
+                return self.validate(&trail).map(|_| trail);
+            }
+        };
+        let mut piles = vec!();
+        let selected = interactions.iter().filter_map(|i| match i {
+            PlayerInteraction::Click(sprite_ref) => self.table.pile_of(&sprite_ref.get_info().card),
+            _ => None
+        });
+        for pile in selected.chain(Some(target)) {
+            if !piles.contains(&pile) {
+                piles.push(pile);
+            }
+        }
+
+        let take = Intent::Take(card.clone(), piles.clone());
+        if self.validate(&take).is_ok() {
+            return Ok(take);
+        }
+        let value = self.build_value(&card, &piles)?;
+        let build = Intent::Build(card, piles, value);
+        self.validate(&build).map(|_| build)
+    }
+
+    /// The value of building `card` on `piles`, which can be no more than a card in hand is worth.
+    fn build_value(&self, card: &Card, piles: &[usize]) -> Result<u8, IntentError> {
+        let value = piles.iter().filter_map(|p| self.pile_value(*p)).map(u32::from).sum::<u32>() + card.table_value() as u32;
+        if value > MAX_HAND_VALUE as u32 {
+            return Err(IntentError::IllegalAction("no card is worth that much"));
+        }
+        Ok(value as u8)
+    }
+
+    /// Every legal intent of the player in turn. Every pile is taken or built on by itself, but
+    /// only the first `MAX_PILES` piles on the table are combined with each other.
+    pub fn legal_intents(&self) -> Vec<Intent> {
+        let player = match self.current_player() {
+            Some(player) => player,
+            None => return vec!()
+        };
+        let piles = self.table.len();
+        let values: Vec<u32> = (0..piles).map(|p| self.pile_value(p).unwrap() as u32).collect();
+        let singles: Vec<Option<u8>> = (0..piles).map(|p| {
+            let cards = self.table.get(p).unwrap().cards();
+            if cards.len() == 1 && !self.is_build(p) { Some(cards[0].value) } else { None }
+        }).collect();
+        let combined = piles.min(MAX_PILES);
+        let selections: Vec<Vec<usize>> = (1..(1u32 << combined))
+            .map(|mask| (0..combined).filter(|p| mask & (1 << p) != 0).collect())
+            .chain((combined..piles).map(|p| vec!(p)))
+            .collect();
+        let hand = player.hand.cards();
+
+        let mut intents = vec!();
+        for card in &hand {
+            let trail = Intent::Trail(card.clone());
+            if self.validate(&trail).is_ok() {
+                intents.push(trail);
             }
-            _ => {
-                self.interactions.clear();
-                Err(IntentError::IllegalAction("yet unknown action"))
+            let target = card.hand_value() as u32;
+            let others: Vec<u32> = hand.iter().filter(|c| *c != card).map(|c| c.hand_value() as u32).collect();
+            for selected in &selections {
+                let sum: u32 = selected.iter().map(|p| values[*p]).sum();
+                let take_sum: u32 = selected.iter()
+                    .map(|p| if singles[*p] == Some(card.value) { target } else { values[*p] })
+                    .sum();
+                if take_sum.is_multiple_of(target) {
+                    let take = Intent::Take(card.clone(), selected.clone());
+                    if self.validate(&take).is_ok() {
+                        intents.push(take);
+                    }
+                }
+                let value = sum + card.table_value() as u32;
+                if others.contains(&value) {
+                    let build = Intent::Build(card.clone(), selected.clone(), value as u8);
+                    if self.validate(&build).is_ok() {
+                        intents.push(build);
+                    }
+                }
             }
+        }
+        intents
+    }
 
+    /// Checks that `intent` is a legal move for the player in turn.
+    pub fn validate(&self, intent: &Intent) -> Result<(), IntentError> {
+        let player = self.current_player().ok_or(IntentError::IllegalAction("the game is not running"))?;
+        let (card, piles) = match intent {
+            Intent::Trail(card) => (card, &[][..]),
+            Intent::Take(card, piles) | Intent::Build(card, piles, _) => (card, &piles[..]),
+        };
+        if !player.hand.contains(card) {
+            return Err(IntentError::IllegalAction("that card is not in your hand"));
+        }
+        for (i, pile) in piles.iter().enumerate() {
+            if *pile >= self.table.len() || piles[..i].contains(pile) {
+                return Err(IntentError::IllegalAction("no such pile on the table"));
+            }
+        }
 
-            /*self.interactions.first().as_ref().ok_or(IntentError::Unknown).and_then(|i| {
-                if self.table.contains(&i.sprite.get_info().card) {
-                    Err(IntentError::PartialIntent("(t) to take"))
+        match intent {
+            Intent::Trail(_) => {
+                let hand = player.hand.cards();
+                let own_build = self.builds.iter()
+                    .any(|b| Some(b.owner) == self.player_turn && hand.iter().any(|c| c.hand_value() == b.value));
+                if own_build {
+                    Err(IntentError::IllegalAction("you must take or extend your build"))
                 } else {
                     Ok(())
                 }
-            })*/
-        } //else {
+            },
+            Intent::Take(card, piles) => {
+                if piles.is_empty() {
+                    return Err(IntentError::IllegalAction("select the piles to take"));
+                }
+                let target = card.hand_value();
+                let mut values = vec!();
+                for pile in piles {
+                    let value = self.pile_value(*pile).unwrap();
+                    let cards = self.table.get(*pile).unwrap().cards();
+                    let same_rank = !self.is_build(*pile) && cards.len() == 1 && cards[0].value == card.value;
+                    if self.is_build(*pile) && value != target {
+                        return Err(IntentError::IllegalAction("a build can only be taken with its value"));
+                    }
+                    if !same_rank && value != target {
+                        values.push(value);
+                    }
+                }
+                if partitions(&values, target) {
+                    Ok(())
+                } else {
+                    Err(IntentError::IllegalAction("the piles do not add up to the card"))
+                }
+            },
+            Intent::Build(card, piles, value) => {
+                if piles.is_empty() {
+                    return Err(IntentError::IllegalAction("select the piles to build on"));
+                }
+                let sum = piles.iter().map(|p| self.pile_value(*p).unwrap() as u32).sum::<u32>() + card.table_value() as u32;
+                if sum != *value as u32 {
+                    return Err(IntentError::IllegalAction("the build does not add up to its value"));
+                }
+                let mut hand = player.hand.cards();
+                if let Some(pos) = hand.iter().position(|c| c == card) {
+                    hand.remove(pos);
+                }
+                if hand.iter().any(|c| c.hand_value() == *value) {
+                    Ok(())
+                } else {
+                    Err(IntentError::IllegalAction("you must hold a card to take the build with"))
+                }
+            },
+        }
+    }
+
+    /// Validates and applies `intent` for the player in turn and passes the turn on.
+    pub fn play(&mut self, intent: Intent) -> Result<(), IntentError> {
+        self.validate(&intent)?;
+        self.redo.clear();
+        self.commit(intent);
+        Ok(())
+    }
+
+    /// Applies an already validated intent. The state before it is remembered if undo is enabled;
+    /// otherwise the piles it empties are released.
+    pub(crate) fn commit(&mut self, intent: Intent) {
+        if self.undo_enabled {
+            self.history.push(self.snapshot());
+        }
+        let number = self.player_turn.unwrap();
+        let mut hand = self.players[number as usize].hand;
+        let mut score = self.players[number as usize].score;
+        self.log.push(LogEntry::Played(number, intent.clone()));
+
+        match &intent {
+            Intent::Trail(card) => {
+                let mut pile = self.table.new_pile(vec!());
+                hand.transfer(card, &mut pile);
+                self.emit(Event::CardTrailed(number, card.clone()));
+            },
+            Intent::Take(card, piles) => {
+                let mut captured = vec!();
+                for mut pile in self.remove_piles(piles) {
+                    captured.append(&mut pile.cards());
+                    pile.transfer_all(&mut score);
+                    self.release(pile);
+                }
+                hand.transfer(card, &mut score);
+                self.last_capture = Some(number);
+                self.emit(Event::PileCaptured(number, card.clone(), captured));
+                let final_play = self.last_round && self.players.iter().all(|p| p.hand.is_empty());
+                if self.table.is_empty() && !final_play {
+                    self.players[number as usize].sweeps += 1;
+                    self.log.push(LogEntry::Swept(number));
+                    self.emit(Event::SweepScored(number));
+                }
+            },
+            Intent::Build(card, piles, value) => {
+                let mut build = self.table.new_pile(vec!());
+                for mut pile in self.remove_piles(piles) {
+                    pile.transfer_all(&mut build);
+                    self.release(pile);
+                }
+                hand.transfer(card, &mut build);
+                self.builds.push(Build {
+                    pile: build,
+                    value: *value,
+                    owner: number,
+                });
+                self.emit(Event::BuildCreated(number, card.clone(), *value));
+            },
+        }
+
+        self.next_turn();
+    }
+
+    /// Unregisters an emptied pile, unless a snapshot may still refer to it.
+    fn release(&self, pile: DeckRef) {
+        if !self.undo_enabled {
+            Deck::release(pile);
+        }
+    }
+
+    fn remove_piles(&mut self, piles: &[usize]) -> Vec<DeckRef> {
+        let mut sorted = piles.to_vec();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        let removed: Vec<_> = sorted.into_iter().map(|p| self.table.remove(p)).collect();
+        self.builds.retain(|b| !removed.contains(&b.pile));
+        removed
+    }
+
+    fn next_turn(&mut self) {
+        if let Some(number) = self.player_turn {
+            self.player_turn = Some((number + 1) % self.players.len() as u8);
+            if self.players.iter().all(|p| p.hand.is_empty()) {
+                self.end_of_round();
+            }
+            if self.player_turn.is_some() {
+                self.emit(Event::TurnChanged(self.player_turn));
+            }
+        }
+    }
+}
+
+/// The rules a game is played by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variant {
+    /// Every sweep scores a point.
+    Standard,
+    /// Sweeps score nothing.
+    NoSweeps,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 2] = [Variant::Standard, Variant::NoSweeps];
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Variant::Standard => write!(f, "standard"),
+            Variant::NoSweeps => write!(f, "no-sweeps"),
+        }
+    }
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Variant::ALL.iter().find(|v| v.to_string() == s).copied().ok_or(format!("unknown variant '{}'", s))
+    }
+}
+
+/// Points of a player at the end of a game, by scoring category.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Points {
+    pub cards: u8,
+    pub spades: u8,
+    pub big_casino: u8,
+    pub little_casino: u8,
+    pub aces: u8,
+    pub sweeps: u8,
+}
+
+impl Points {
+    pub fn total(&self) -> u8 {
+        self.cards + self.spades + self.big_casino + self.little_casino + self.aces + self.sweeps
+    }
+
+    /// The points of a player's breakdown, added up by category.
+    pub fn from_breakdown(breakdown: &[Scored]) -> Points {
+        let mut points = Points::default();
+        for scored in breakdown {
+            *match scored.category {
+                Category::Cards => &mut points.cards,
+                Category::Spades => &mut points.spades,
+                Category::BigCasino => &mut points.big_casino,
+                Category::LittleCasino => &mut points.little_casino,
+                Category::Aces => &mut points.aces,
+                Category::Sweeps => &mut points.sweeps,
+            } += scored.points;
+        }
+        points
+    }
+}
+
+/// The categories points are scored in, in the order they are counted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Category {
+    Cards,
+    Spades,
+    BigCasino,
+    LittleCasino,
+    Aces,
+    Sweeps,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [Category::Cards, Category::Spades, Category::BigCasino,
+                                    Category::LittleCasino, Category::Aces, Category::Sweeps];
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Category::Cards => "cards",
+            Category::Spades => "spades",
+            Category::BigCasino => "big casino",
+            Category::LittleCasino => "little casino",
+            Category::Aces => "aces",
+            Category::Sweeps => "sweeps",
+        })
+    }
+}
+
+/// What a player scored in one category: the captured cards that count towards it, how many
+/// there are, or how many sweeps, and the points awarded for them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scored {
+    pub category: Category,
+    pub cards: Vec<Card>,
+    pub count: usize,
+    pub points: u8,
+}
+
+impl Game {
+    /// Counts the points of every player from their score piles: 1 for most cards, 2 for most
+    /// spades, 2 for big casino, 1 for little casino, 1 per ace and 1 per sweep, unless the
+    /// variant does not count sweeps.
+    pub fn points(&self) -> Vec<Points> {
+        self.breakdown().iter().map(|b| Points::from_breakdown(b)).collect()
+    }
+
+    /// The points of every player, category by category in the order of `Category::ALL`, with
+    /// the cards each category is scored for.
+    pub fn breakdown(&self) -> Vec<Vec<Scored>> {
+        let captured: Vec<Vec<Card>> = self.players.iter().map(|p| p.score.cards()).collect();
+        let most = |count: &dyn Fn(&Vec<Card>) -> usize| -> Option<usize> {
+            let counts: Vec<usize> = captured.iter().map(count).collect();
+            let max = *counts.iter().max()?;
+            if counts.iter().filter(|c| **c == max).count() == 1 {
+                counts.iter().position(|c| *c == max)
+            } else {
+                None
+            }
+        };
+        let most_cards = most(&|cards| cards.len());
+        let most_spades = most(&|cards| cards.iter().filter(|c| c.suit == Suit::SPADES).count());
+
+        captured.iter().enumerate().map(|(i, cards)| {
+            let scored = |category: Category, keep: &dyn Fn(&Card) -> bool, points: &dyn Fn(usize) -> u8| {
+                let cards: Vec<Card> = cards.iter().filter(|c| keep(c)).cloned().collect();
+                Scored { category, count: cards.len(), points: points(cards.len()), cards }
+            };
+            let sweeps = self.players[i].sweeps;
+            vec!(
+                scored(Category::Cards, &|_| true, &|_| if most_cards == Some(i) { 1 } else { 0 }),
+                scored(Category::Spades, &|c| c.suit == Suit::SPADES, &|_| if most_spades == Some(i) { 2 } else { 0 }),
+                scored(Category::BigCasino, &|c| c.hand_value() == 16, &|n| 2 * n as u8),
+                scored(Category::LittleCasino, &|c| c.hand_value() == 15, &|n| n as u8),
+                scored(Category::Aces, &|c| c.hand_value() == 14, &|n| n as u8),
+                Scored {
+                    category: Category::Sweeps,
+                    cards: vec!(),
+                    count: sweeps as usize,
+                    points: if self.variant == Variant::NoSweeps { 0 } else { sweeps },
+                },
+            )
+        }).collect()
+    }
+
+    /// The points of every player over the match so far, counting the deal being played.
+    pub fn standings(&self) -> Vec<u32> {
+        self.points().iter().enumerate().map(|(i, points)| {
+            self.deals.iter().map(|deal| deal[i] as u32).sum::<u32>() + points.total() as u32
+        }).collect()
+    }
+
+    /// The winner of the match, once a deal is over and a single player has the most points and
+    /// at least the target. A single deal is won by the player with the most points.
+    pub fn match_winner(&self) -> Option<u8> {
+        if self.player_turn.is_some() || self.round == 0 {
+            return None;
+        }
+        let standings = self.standings();
+        let best = *standings.iter().max()?;
+        if best < self.target as u32 || standings.iter().filter(|s| **s == best).count() > 1 {
+            return None;
+        }
+        standings.iter().position(|s| *s == best).map(|i| i as u8)
+    }
 
-            //
-        //}
+    /// Whether a match is on and another deal is to be played: the deal is over, and nobody has
+    /// won yet.
+    pub fn deal_pending(&self) -> bool {
+        self.target > 0 && self.round > 0 && self.player_turn.is_none() && self.match_winner().is_none()
+    }
+
+    /// Deals the next hand of a match. The points of the deal just played are recorded, and the
+    /// players start again with empty score piles from a new deck.
+    pub fn next_deal(&mut self) {
+        self.deals.push(self.points().iter().map(|p| p.total()).collect());
+        Deck::release(self.deck);
+        for pile in self.table.piles() {
+            Deck::release(*pile);
+        }
+        for p in &mut self.players {
+            Deck::release(p.score);
+            p.score = Deck::empty();
+            p.sweeps = 0;
+        }
+        self.deck = self.shuffled_deck();
+        self.table = Table::new(vec!());
+        self.builds.clear();
+        self.last_round = false;
+        self.last_capture = None;
+        self.log.clear();
+        self.history.clear();
+        self.redo.clear();
+        self.start();
+    }
+
+    /// A new deck for the next deal. Every deal of a seeded match is shuffled the same way each
+    /// time the match is played.
+    pub fn shuffled_deck(&self) -> DeckRef {
+        match self.seed {
+            Some(seed) => Deck::shuffled(&mut StdRng::seed_from_u64(seed.wrapping_add(self.deals.len() as u64))),
+            None => Deck::shuffled(&mut rand::thread_rng()),
+        }
     }
 }
 
+impl Card {
+    /// The value of the card when it lies on the table.
+    pub fn table_value(&self) -> u8 {
+        self.value
+    }
+
+    /// The value of the card when played from hand: aces count 14, little casino (2 of spades)
+    /// 15 and big casino (10 of diamonds) 16.
+    pub fn hand_value(&self) -> u8 {
+        match (&self.suit, self.value) {
+            (_, 1) => 14,
+            (Suit::SPADES, 2) => 15,
+            (Suit::DIAMONDS, 10) => 16,
+            (_, value) => value,
+        }
+    }
+}
+
+/// The most a card is worth when played from hand, which big casino is.
+pub const MAX_HAND_VALUE: u8 = 16;
+
+/// The most table piles the move generator will combine.
+pub const MAX_PILES: usize = 12;
+
+/// Whether `values` can be split into groups that each add up to `target`.
+pub(crate) fn partitions(values: &[u8], target: u8) -> bool {
+    if values.is_empty() {
+        return true;
+    }
+    let rest = &values[1..];
+    for mask in 0..(1u32 << rest.len()) {
+        let mut sum = values[0] as u32;
+        let mut remaining = vec!();
+        for (i, v) in rest.iter().enumerate() {
+            if mask & (1 << i) != 0 {
+                sum += *v as u32;
+            } else {
+                remaining.push(*v);
+            }
+        }
+        if sum == target as u32 && partitions(&remaining, target) {
+            return true;
+        }
+    }
+    false
+}
+
+#[derive(Debug)]
 pub enum IntentError {
     PartialIntent(&'static str),
     IllegalAction(&'static str),
     Unknown
 }
 
+#[derive(Debug)]
 pub enum KasinoError {
     OtherPlayersCards,
     DeckOrPileEmpty
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Deck;
+
+    fn card(suit: Suit, value: u8) -> Card {
+        Card::new(suit, value, Deck::empty())
+    }
+
+    fn game_with(hand: Vec<Card>, table: Vec<Card>) -> Game {
+        let mut player = Player::new(0, "player1");
+        player.deal(&mut hand.clone());
+        let mut game = Game {
+            players: vec!(player, Player::new(1, "player2")),
+            player_turn: Some(0),
+            ..Default::default()
+        };
+        for c in table {
+            game.table.new_pile(vec!(c));
+        }
+        game
+    }
+
+    #[test]
+    fn test_partitions() {
+        assert!(partitions(&[], 10));
+        assert!(partitions(&[3, 7, 4, 6], 10));
+        assert!(!partitions(&[3, 7, 4], 10));
+    }
+
+    #[test]
+    fn test_take_and_sweep() {
+        let mut game = game_with(
+            vec!(card(Suit::HEARTS, 9), card(Suit::CLUBS, 3)),
+            vec!(card(Suit::CLUBS, 4), card(Suit::SPADES, 5)),
+        );
+        assert!(game.validate(&Intent::Take(card(Suit::HEARTS, 9), vec!(0))).is_err());
+        game.play(Intent::Take(card(Suit::HEARTS, 9), vec!(0, 1))).unwrap();
+        assert!(game.table.is_empty());
+        assert_eq!(game.players[0].sweeps, 1);
+        assert_eq!(game.players[0].score.len(), 3);
+        assert_eq!(game.player_turn, Some(1));
+    }
+
+    #[test]
+    fn test_legal_intents() {
+        let game = game_with(
+            vec!(card(Suit::HEARTS, 9), card(Suit::CLUBS, 4)),
+            vec!(card(Suit::CLUBS, 5), card(Suit::SPADES, 4)),
+        );
+        let intents = game.legal_intents();
+        assert!(intents.contains(&Intent::Trail(card(Suit::CLUBS, 4))));
+        assert!(intents.contains(&Intent::Take(card(Suit::HEARTS, 9), vec!(0, 1))));
+        assert!(intents.contains(&Intent::Take(card(Suit::CLUBS, 4), vec!(1))));
+        assert!(intents.contains(&Intent::Build(card(Suit::CLUBS, 4), vec!(0), 9)));
+        assert!(!intents.contains(&Intent::Take(card(Suit::HEARTS, 9), vec!(0))));
+        assert!(intents.iter().all(|i| game.validate(i).is_ok()));
+
+        // piles past those combined can still be taken by themselves
+        let mut game = game_with(vec!(card(Suit::HEARTS, 9)), vec!());
+        for _ in 0..MAX_PILES {
+            game.table.new_pile(vec!(card(Suit::CLUBS, 13)));
+        }
+        game.table.new_pile(vec!(card(Suit::SPADES, 9)));
+        assert!(game.legal_intents().contains(&Intent::Take(card(Suit::HEARTS, 9), vec!(MAX_PILES))));
+    }
+
+    #[test]
+    fn test_points() {
+        let mut game = game_with(vec!(), vec!());
+        game.players[0].score.append(&mut vec!(card(Suit::SPADES, 2), card(Suit::SPADES, 5), card(Suit::HEARTS, 1)));
+        game.players[1].score.append(&mut vec!(card(Suit::DIAMONDS, 10), card(Suit::CLUBS, 4), card(Suit::CLUBS, 1)));
+        game.players[1].sweeps = 1;
+        let points = game.points();
+        assert_eq!(points[0], Points { spades: 2, little_casino: 1, aces: 1, ..Default::default() });
+        assert_eq!(points[1].total(), 4);
+        game.variant = Variant::NoSweeps;
+        assert_eq!(game.points()[1].total(), 3);
+
+        let breakdown = game.breakdown();
+        assert_eq!(breakdown[0][1], Scored {
+            category: Category::Spades,
+            cards: vec!(card(Suit::SPADES, 2), card(Suit::SPADES, 5)),
+            count: 2,
+            points: 2,
+        });
+        assert_eq!(breakdown[1][5].count, 1);
+        assert_eq!(breakdown[1][5].points, 0);
+    }
+
+    #[test]
+    fn test_match_of_deals() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            target: 100,
+            seed: Some(7),
+            ..Default::default()
+        };
+        game.start();
+        while game.player_turn.is_some() {
+            let intent = game.legal_intents()[0].clone();
+            game.play(intent).unwrap();
+        }
+        assert!(game.deal_pending());
+        let standings = game.standings();
+        game.next_deal();
+        assert_eq!(game.deals.len(), 1);
+        assert_eq!(game.standings(), standings);
+        assert_eq!(game.player_turn, Some(0));
+        assert!(game.players.iter().all(|p| p.score.is_empty() && p.hand.len() == 4));
+
+        game.target = 1;
+        assert_eq!(game.match_winner(), None);
+        game.player_turn = None;
+        assert!(game.match_winner().is_some() || standings[0] == standings[1]);
+        game.discard();
+    }
+
+    #[test]
+    fn test_build_needs_card_in_hand() {
+        let mut game = game_with(
+            vec!(card(Suit::HEARTS, 2), card(Suit::CLUBS, 7)),
+            vec!(card(Suit::CLUBS, 5)),
+        );
+        assert!(game.validate(&Intent::Build(card(Suit::HEARTS, 2), vec!(0), 8)).is_err());
+        game.play(Intent::Build(card(Suit::HEARTS, 2), vec!(0), 7)).unwrap();
+        assert_eq!(game.builds.len(), 1);
+        assert_eq!(game.pile_value(0), Some(7));
+        assert_eq!(game.player_turn, Some(1));
+    }
+
+    #[test]
+    fn test_drop_intent() {
+        let game = game_with(
+            vec!(card(Suit::HEARTS, 2), card(Suit::CLUBS, 7), card(Suit::SPADES, 9)),
+            vec!(card(Suit::CLUBS, 5), card(Suit::DIAMONDS, 9)),
+        );
+        assert_eq!(game.drop_intent(&[], card(Suit::HEARTS, 2), None).ok(), Some(Intent::Trail(card(Suit::HEARTS, 2))));
+        assert_eq!(game.drop_intent(&[], card(Suit::SPADES, 9), Some(1)).ok(), Some(Intent::Take(card(Suit::SPADES, 9), vec!(1))));
+        assert_eq!(game.drop_intent(&[], card(Suit::HEARTS, 2), Some(0)).ok(), Some(Intent::Build(card(Suit::HEARTS, 2), vec!(0), 7)));
+        assert!(game.drop_intent(&[], card(Suit::CLUBS, 7), Some(1)).is_err());
+
+        // a build is never worth more than a card
+        let game = game_with(vec!(card(Suit::HEARTS, 12)), vec!(card(Suit::CLUBS, 13)));
+        assert!(matches!(game.drop_intent(&[], card(Suit::HEARTS, 12), Some(0)), Err(IntentError::IllegalAction("no card is worth that much"))));
+    }
+}
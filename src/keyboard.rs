@@ -0,0 +1,158 @@
+//! Plays the game from the keyboard alone. A cursor goes over the cards in the hand of the player
+//! in turn and the piles on the table; cards under it are selected as if they were clicked.
+
+use crate::graphic::{SpriteRef, Ui};
+use crate::types::{Game, PlayerInteraction};
+use piston::Key;
+
+/// The card or pile under the keyboard cursor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Focus {
+    Hand(usize),
+    Pile(usize),
+}
+
+/// What the keys do, shown to the player in turn.
+pub const HINTS: &str = "arrows or 1-9 move, space selects, c captures, b builds, t trails, enter plays, esc cancels";
+
+impl Ui {
+    pub fn keyboard(&mut self, game: &mut Game, key: Key) {
+        if self.screened(game) {
+            self.revealed = game.player_turn;
+            return;
+        }
+        if !self.may_play(game) {
+            return;
+        }
+        let hand = game.current_player().map(|p| p.hand.len()).unwrap_or(0);
+        let piles = game.table.len();
+        match key {
+            Key::Left | Key::Right => {
+                let step = |i: usize, n: usize| if key == Key::Left { (i + n - 1) % n } else { (i + 1) % n };
+                self.focus = match self.focus(game) {
+                    Some(Focus::Hand(i)) => Some(Focus::Hand(step(i, hand))),
+                    Some(Focus::Pile(i)) => Some(Focus::Pile(step(i, piles))),
+                    None if hand > 0 => Some(Focus::Hand(0)),
+                    None => None,
+                };
+            },
+            Key::Up if piles > 0 => self.focus = Some(Focus::Pile(0)),
+            Key::Down if hand > 0 => self.focus = Some(Focus::Hand(0)),
+            Key::D1 | Key::D2 | Key::D3 | Key::D4 | Key::D5 | Key::D6 | Key::D7 | Key::D8 | Key::D9 => {
+                let number = key as usize - Key::D1 as usize;
+                self.focus = match self.focus(game) {
+                    Some(Focus::Pile(_)) if number < piles => Some(Focus::Pile(number)),
+                    Some(Focus::Pile(_)) => return,
+                    _ if number < hand => Some(Focus::Hand(number)),
+                    _ => return,
+                };
+                self.select(game);
+            },
+            Key::Space => self.select(game),
+            Key::Return => self.interact(game),
+            Key::Escape => {
+                self.interactions.clear();
+                self.message = None;
+            },
+            Key::C | Key::B | Key::T | Key::D => {
+                self.interactions.push(PlayerInteraction::Keyboard(key));
+                self.interact(game);
+            },
+            _ => {}
+        }
+    }
+
+    /// The cursor, kept on the cards there are.
+    pub fn focus(&self, game: &Game) -> Option<Focus> {
+        let hand = game.current_player().map(|p| p.hand.len()).unwrap_or(0);
+        match self.focus? {
+            Focus::Hand(_) if hand == 0 => None,
+            Focus::Hand(i) => Some(Focus::Hand(i.min(hand - 1))),
+            Focus::Pile(_) if game.table.is_empty() => None,
+            Focus::Pile(i) => Some(Focus::Pile(i.min(game.table.len() - 1))),
+        }
+    }
+
+    /// The sprite of the card under the cursor; the top card of a pile.
+    pub fn focused_sprite(&self, game: &Game) -> Option<SpriteRef> {
+        let deck = match self.focus(game)? {
+            Focus::Hand(_) => game.current_player()?.hand,
+            Focus::Pile(i) => game.table.get(i)?,
+        };
+        let cards = deck.cards();
+        let card = match self.focus(game)? {
+            Focus::Hand(i) => cards.get(i)?,
+            Focus::Pile(_) => cards.last()?,
+        };
+        card.sprite
+    }
+
+    /// Selects the card under the cursor, or lets go of it if it was selected. Only one card of
+    /// the hand is selected at a time.
+    fn select(&mut self, game: &mut Game) {
+        let sprite_ref = match self.focused_sprite(game) {
+            Some(sprite_ref) => sprite_ref,
+            None => return
+        };
+        let clicked = PlayerInteraction::Click(sprite_ref);
+        if self.interactions.contains(&clicked) {
+            self.interactions.retain(|i| *i != clicked);
+            return;
+        }
+        if let Some(Focus::Hand(_)) = self.focus(game) {
+            let hand = game.current_player().map(|p| p.hand);
+            self.interactions.retain(|i| match i {
+                PlayerInteraction::Click(s) => !hand.is_some_and(|h| h.contains(&s.get_info().card)),
+                _ => true
+            });
+        }
+        self.interactions.push(clicked);
+        self.interact(game);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphic::SpriteInfo;
+    use crate::types::{Card, Player};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_capture_with_keys() {
+        let card = |s: &str| s.parse::<Card>().unwrap();
+        let mut player = Player::new(0, "player1");
+        player.deal(&mut vec!(card("H:2"), card("C:7"), card("S:9")));
+        let mut game = Game {
+            players: vec!(player, Player::new(1, "player2")),
+            player_turn: Some(0),
+            ..Default::default()
+        };
+        game.table.new_pile(vec!(card("C:5")));
+        game.table.new_pile(vec!(card("D:9")));
+        let mut decks = vec!(game.players[0].hand);
+        decks.extend_from_slice(game.table.piles());
+        for mut deck in decks {
+            for c in &mut deck.iter() {
+                c.sprite = Some(SpriteRef::new(Uuid::new(uuid::UuidVersion::Random).unwrap(), SpriteInfo { card: c.clone() }));
+            }
+        }
+        let mut ui = Ui::default();
+
+        ui.keyboard(&mut game, Key::Right);
+        ui.keyboard(&mut game, Key::Right);
+        assert_eq!(ui.focus(&game), Some(Focus::Hand(1)));
+        ui.keyboard(&mut game, Key::Space);
+        ui.keyboard(&mut game, Key::Escape);
+        assert!(ui.interactions.is_empty());
+
+        ui.keyboard(&mut game, Key::D3);
+        ui.keyboard(&mut game, Key::Up);
+        ui.keyboard(&mut game, Key::Left);
+        assert_eq!(ui.focus(&game), Some(Focus::Pile(1)));
+        ui.keyboard(&mut game, Key::Space);
+        // taking the pile of the same value is the only play, and is made at once
+        assert_eq!(game.players[0].score.len(), 2);
+        assert_eq!(game.table.len(), 1);
+    }
+}
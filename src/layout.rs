@@ -0,0 +1,132 @@
+//! Where the cards go in the window. Every seat has a row, with its score pile first and its hand
+//! after it, and the table has a row in between the seats. Cards are scaled to fit the rows, and
+//! overlap when a row has more cards than fit side by side.
+
+/// The size of a card before the layout scales it. Card art of other sizes is drawn this size.
+pub const CARD_WIDTH: f64 = 180.0;
+pub const CARD_HEIGHT: f64 = 270.0;
+const MARGIN: f64 = 10.0;
+/// The gap between cards side by side, and how far apart the cards of a pile are fanned, in
+/// card widths.
+const GAP: f64 = 0.1;
+const FAN: f64 = 0.2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    /// The scale of every card.
+    pub scale: f64,
+    width: f64,
+    top: f64,
+    row: f64,
+    /// The seats in the rows above the table.
+    above: usize,
+}
+
+impl Layout {
+    /// Lays out `seats` in a window of `width` and `height`, below a band `top` high kept for
+    /// text.
+    pub fn new(width: f64, height: f64, top: f64, seats: usize) -> Layout {
+        let row = ((height - top - MARGIN) / (seats + 1) as f64).max(1.0);
+        // cards fill most of a row, but leave room for at least a few side by side
+        let scale = (0.85 * row / CARD_HEIGHT).min((width - 2.0 * MARGIN) / (4.0 * CARD_WIDTH)).max(0.05);
+        Layout {
+            scale,
+            width,
+            top,
+            row,
+            above: seats.div_ceil(2),
+        }
+    }
+
+    pub fn card_size(&self) -> (f64, f64) {
+        (CARD_WIDTH * self.scale, CARD_HEIGHT * self.scale)
+    }
+
+    fn middle(&self, row: usize) -> f64 {
+        self.top + (row as f64 + 0.5) * self.row
+    }
+
+    fn seat_row(&self, seat: usize) -> usize {
+        if seat < self.above { seat } else { seat + 1 }
+    }
+
+    /// The centre of the score pile of `seat`.
+    pub fn score(&self, seat: usize) -> [f64; 2] {
+        [MARGIN + self.card_size().0 / 2.0, self.middle(self.seat_row(seat))]
+    }
+
+    /// Where the deck lies, and cards are dealt from: before the table, below the score piles
+    /// of the seats above it.
+    pub fn deck(&self) -> [f64; 2] {
+        [MARGIN + self.card_size().0 / 2.0, self.middle(self.above)]
+    }
+
+    /// The centres of the cards in the hand of `seat`.
+    pub fn hand(&self, seat: usize, cards: usize) -> Vec<[f64; 2]> {
+        let y = self.middle(self.seat_row(seat));
+        self.spread(&vec!(1; cards)).into_iter().map(|p| [p[0][0], y]).collect()
+    }
+
+    /// The centres of the cards of each pile on the table, given the number of cards in each.
+    pub fn table(&self, piles: &[usize]) -> Vec<Vec<[f64; 2]>> {
+        let y = self.middle(self.above);
+        let (_, height) = self.card_size();
+        // piles are fanned a little down to the right, around the middle of the row
+        let drop = |j: usize| FAN * height / 4.0 * (j as f64).min(2.0);
+        self.spread(piles).into_iter().map(|pile| {
+            let lift = drop(pile.len().saturating_sub(1)) / 2.0;
+            pile.iter().enumerate().map(|(j, p)| [p[0], y - lift + drop(j)]).collect()
+        }).collect()
+    }
+
+    /// Spreads groups of fanned cards over the row after the score pile, from left to right.
+    /// When they do not fit, every step is shortened so the groups overlap.
+    fn spread(&self, groups: &[usize]) -> Vec<Vec<[f64; 2]>> {
+        let (width, _) = self.card_size();
+        let start = MARGIN + width * (1.0 + GAP) + width / 2.0;
+        // the steps from one card to the next, inside groups and between them
+        let steps: f64 = groups.iter().map(|n| FAN * n.saturating_sub(1) as f64 * width).sum::<f64>()
+            + groups.len().saturating_sub(1) as f64 * width * (1.0 + GAP);
+        let room = self.width - MARGIN - width / 2.0 - start;
+        let shrink = if steps > room && steps > 0.0 { (room / steps).max(0.0) } else { 1.0 };
+
+        let mut x = start;
+        groups.iter().map(|n| {
+            let group: Vec<[f64; 2]> = (0..*n).map(|j| [x + FAN * width * shrink * j as f64, 0.0]).collect();
+            x += (FAN * n.saturating_sub(1) as f64 + 1.0 + GAP) * width * shrink;
+            group
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inside(layout: &Layout, centre: [f64; 2], width: f64, height: f64) -> bool {
+        let (w, h) = layout.card_size();
+        centre[0] - w / 2.0 >= 0.0 && centre[0] + w / 2.0 <= width && centre[1] - h / 2.0 >= 0.0 && centre[1] + h / 2.0 <= height
+    }
+
+    #[test]
+    fn test_everything_stays_in_the_window() {
+        for (width, height) in [(300.0, 300.0), (1024.0, 768.0), (1920.0, 600.0)] {
+            let layout = Layout::new(width, height, 100.0, 2);
+            for cards in [1, 4, 20] {
+                for seat in 0..2 {
+                    assert!(layout.hand(seat, cards).into_iter().all(|c| inside(&layout, c, width, height)));
+                    assert!(inside(&layout, layout.score(seat), width, height));
+                }
+                let table = layout.table(&vec!(3; cards));
+                assert!(table.into_iter().flatten().all(|c| inside(&layout, c, width, height)));
+            }
+        }
+
+        // hands that fit are not squeezed, and the table lies between the seats
+        let layout = Layout::new(1024.0, 768.0, 100.0, 2);
+        let hand = layout.hand(0, 4);
+        assert!((hand[1][0] - hand[0][0] - layout.card_size().0 * (1.0 + GAP)).abs() < 1e-9);
+        assert!(layout.score(0)[1] < layout.table(&[1])[0][0][1]);
+        assert!(layout.table(&[1])[0][0][1] < layout.score(1)[1]);
+    }
+}
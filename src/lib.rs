@@ -0,0 +1,30 @@
+// card suits and texture kinds are spelled in capitals, and decks are created through `Deck::new`
+#![allow(clippy::upper_case_acronyms, clippy::new_ret_no_self)]
+
+#[macro_use]
+extern crate lazy_static;
+
+extern crate piston_window;
+extern crate ai_behavior;
+extern crate sprite;
+extern crate find_folder;
+
+
+pub mod types;
+pub mod graphic;
+pub mod kasino;
+pub mod replay;
+pub mod history;
+pub mod events;
+pub mod ai;
+pub mod net;
+pub mod view;
+pub mod terminal;
+pub mod layout;
+pub mod hit;
+pub mod keyboard;
+pub mod setup;
+pub mod menu;
+pub mod summary;
+pub mod assets;
+pub mod svg;
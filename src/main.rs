@@ -1,41 +1,92 @@
-#[macro_use]
-extern crate lazy_static;
-
 extern crate piston_window;
 extern crate ai_behavior;
 extern crate sprite;
 extern crate find_folder;
 
-
-mod types;
-mod graphic;
-mod kasino;
-
 use std::rc::Rc;
+use std::cell::Cell;
 
 use piston_window::*;
 use sprite::*;
-use ai_behavior::{
-    Action,
-    Sequence,
-    Wait,
-    WaitForever,
-    While,
-};
 use sdl2_window::Sdl2Window;
-use graphics::rectangle::{square, rectangle_by_corners};
-use crate::types::{Game, Player, Deck};
-use crate::graphic::{GraphicsEnv, TextureKind, SpriteRef};
-use opengl_graphics::GlGraphics;
-use std::collections::HashMap;
-use std::path::Path;
+use rcards::types::{Game, Viewer};
+use rcards::graphic::{GraphicsEnv, Ui};
+use rcards::replay::Replay;
+use rcards::events::Event;
+use rcards::ai;
+use rcards::ai::solver::Analysis;
+use rcards::kasino::IntentError;
+use rcards::net::{Server, Client};
+use rcards::setup::{Setup, MAX_SEATS};
+use rcards::menu::{Menu, Page, Action};
+use rcards::assets::Pack;
+use opengl_graphics::{GlGraphics, Texture};
+
+/// How the window shows the games played in it, as given on the command line.
+struct Options {
+    watch: bool,
+    open_hands: bool,
+    undo: bool,
+    animation: Option<f64>,
+    verbose: bool,
+}
 
 fn main() {
-    let (width, height) = (300, 300);
+    // seats played by the computer are given as `--ai <seat>[:easy|medium|hard|mcts[:<iterations>]|expert|bot:<command>]`,
+    // and the setup of a new game starts from them
+    let args: Vec<String> = std::env::args().collect();
+    let mut setup = Setup::default();
+    for pair in args.windows(2).filter(|pair| pair[0] == "--ai") {
+        let (seat, spec) = pair[1].split_once(':').unwrap_or((&pair[1], "medium"));
+        let number = match seat.parse::<usize>() {
+            Ok(number) if (1..=MAX_SEATS).contains(&number) => number,
+            _ => usage(&format!("unknown seat '{}', seats are numbered from 1 to {}", seat, MAX_SEATS))
+        };
+        if let Err(e) = ai::from_spec(spec) {
+            usage(&e);
+        }
+        if number > setup.seats.len() {
+            setup.set_seats(number);
+        }
+        setup.seats[number - 1].ai = Some(spec.to_string());
+    }
+    let option = |name: &str| args.windows(2).find(|pair| pair[0] == name).map(|pair| pair[1].clone());
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    // `--watch` watches the game without a seat, and `--open-hands` lets those watching see every hand
+    let open_hands = flag("--open-hands");
+
+    // `--serve <address>` hosts the game for players on other machines instead of opening a
+    // window; the seats not played by the computer go to the first clients to join, and with
+    // `--takeover <seconds>[:<controller>]` the computer plays for those who drop until they rejoin
+    if let Some(address) = option("--serve") {
+        let mut game = setup.game().unwrap_or_else(|e| usage(&e));
+        game.undo_enabled = false;
+        let mut server = Server::bind(&address, game).unwrap();
+        server.open_hands = open_hands;
+        if let Some(takeover) = option("--takeover") {
+            let (seconds, spec) = match takeover.split_once(':') {
+                Some((seconds, spec)) => (seconds, Some(spec)),
+                None => (takeover.as_str(), None),
+            };
+            let seconds = seconds.parse().unwrap_or_else(|_| usage(&format!("--takeover takes seconds, not '{}'", seconds)));
+            server.takeover = Some(std::time::Duration::from_secs(seconds));
+            if let Some(spec) = spec {
+                if let Err(e) = ai::from_spec(spec) {
+                    usage(&e);
+                }
+                server.stand_in = spec.to_string();
+            }
+        }
+        println!("serving on {}", server.local_addr());
+        server.run();
+        return;
+    }
+
+    let (width, height) = (1024, 768);
     let opengl = OpenGL::V3_2;
     let mut window: PistonWindow<Sdl2Window> =
         WindowSettings::new("kasino", (width, height))
-            .exit_on_esc(true)
+            .exit_on_esc(false)
             .graphics_api(opengl)
             .vsync(false)
             .build()
@@ -99,48 +150,299 @@ fn main() {
 
 
     // create scene
-    let mut scene: Scene<opengl_graphics::Texture> = Scene::new();
+    let mut scene: Scene<Texture> = Scene::new();
 
-    // create players
-    let player1 = Player::new(0, "player1");
-    let player2 = Player::new(1, "player2");
+    // `--animation <seconds>` sets how long cards take to move, and 0 turns the animations off;
+    // `--verbose` prints every event of the game
+    let options = Options {
+        watch: flag("--watch"),
+        open_hands,
+        undo: !flag("--no-undo"),
+        animation: option("--animation").map(|time| time.parse()
+            .unwrap_or_else(|_| usage(&format!("--animation takes seconds, not '{}'", time)))),
+        verbose: flag("--verbose"),
+    };
+    // the pause menu saves the game to, and loads it from, `--save <path>`
+    let save_path = option("--save").unwrap_or("kasino.save".to_string());
+    // lay out the table again only when the game has changed
+    let changed = Rc::new(Cell::new(true));
+    // what the game has to tell the window, e.g. that the computer made an illegal play
+    let told = Rc::new(Cell::new(None));
 
-    // create game
-    let graphics_env = GraphicsEnv::new(GlGraphics::new(opengl));
-    let mut game = Game::new(graphics_env, vec![player1, player2]);
-    game.start();
-    game.prepare(&mut scene);
+    // the window opens with the main menu, unless it joins a game hosted elsewhere with
+    // `--connect <address> [--seat <seat>]`; a dropped seat is taken back with
+    // `--connect <address> --token <token>`
+    let mut menu = Menu::new(setup);
+    // `--cards <pack>` draws the cards with an asset pack from the assets folder, and `--back <name>`
+    // with one of its backs
+    let mut pack = Pack::find(option("--cards").as_deref()).unwrap_or_else(|e| usage(&e));
+    if let Some(back) = option("--back") {
+        pack.choose_back(&back).unwrap_or_else(|e| usage(&e));
+    }
+    let graphics_env = GraphicsEnv::new(GlGraphics::new(opengl), &pack);
+    let mut client = None;
+    let (mut game, mut ui) = match option("--connect") {
+        Some(address) => {
+            let seat = option("--seat").map(|seat| seat.parse::<u8>().ok().and_then(|seat| seat.checked_sub(1))
+                .unwrap_or_else(|| usage(&format!("unknown seat '{}', seats are numbered from 1", seat))));
+            let (c, game) = if flag("--watch") {
+                Client::watch(&address).unwrap()
+            } else if let Some(token) = option("--token") {
+                Client::rejoin(&address, &token).unwrap()
+            } else {
+                Client::connect(&address, seat).unwrap()
+            };
+            if let Some(token) = &c.token {
+                println!("joined; rejoin with --token {}", token);
+            }
+            let ui = Ui {
+                graphics_env: Some(graphics_env),
+                ..c.ui()
+            };
+            client = Some(c);
+            menu.hosted = true;
+            menu.close();
+            open(game, ui, &options, true, &changed, &told, &mut scene)
+        },
+        // an empty table lies behind the main menu until a game is started
+        None => (Game::default(), Ui {
+            graphics_env: Some(graphics_env),
+            ..Default::default()
+        }),
+    };
     
-    let window_size = window.size();
+    // the table is laid out again for the new size when the window is resized
+    let mut window_size = window.size();
 
     // init rendering
     let mut settings = EventSettings::new();
     settings.max_fps = 30;
     let mut events = Events::new(settings);
     let mut mouse_pos = [0.0,0.0];
+
+    // replay of the current game, toggled with (r) and stepped with the arrow keys; it is shown
+    // with every hand face up
+    let mut replay: Option<(Replay, Ui)> = None;
+    let mut replay_scene: Scene<Texture> = Scene::new();
+    // undo with ctrl+z, redo with ctrl+y
+    let mut ctrl = false;
+    // time since the last play, so plays by the computer can be followed
+    let mut idle = 0.0;
+    // the computer thinks on a thread of its own, so the window is drawn meanwhile
+    let mut thinking = None;
+    // the analysis on (a) goes a play at a time, for the same reason
+    let mut analyses: Vec<(String, Analysis)> = vec!();
     while let Some(e) = events.next(&mut window) {
+        scene.event(&e);
+        replay_scene.event(&e);
+        if let Some(args) = e.update_args() {
+            idle += args.dt;
+            // the computer waits for the cards to stop moving before it plays, and while the game is paused
+            if replay.is_none() && !menu.is_open() {
+                match &thinking {
+                    Some(t) => if let Some(played) = game.conclude(t) {
+                        thinking = None;
+                        if played {
+                            idle = 0.0;
+                        }
+                    },
+                    None if idle > 0.8 && scene.running() == 0 => thinking = game.think(),
+                    None => {}
+                }
+            }
+            if !analyses.is_empty() && !analyses.iter_mut().any(|(_, a)| a.step()) {
+                let mistakes: Vec<String> = analyses.drain(..).flat_map(|(name, analysis)| {
+                    analysis.mistakes().into_iter().map(move |mistake| format!(
+                        "{}: play {} was {}, {} would have been {} points better",
+                        name, mistake.position + 1, mistake.played, mistake.best, mistake.lost))
+                }).collect();
+                ui.message = Some(if mistakes.is_empty() {
+                    "no play cost any points".to_string()
+                } else {
+                    mistakes.join("\n")
+                });
+            }
+            if let Some(c) = client.as_mut() {
+                if c.sync(&mut game, &mut ui, &mut scene) {
+                    changed.set(true);
+                }
+            }
+            if let Some(message) = told.take() {
+                ui.message = Some(message);
+            }
+        }
+        if let Some(args) = e.resize_args() {
+            window_size = args.window_size.into();
+            changed.set(true);
+            replay_scene = Scene::new();
+        }
+        if let Some(Button::Keyboard(Key::LCtrl)) | Some(Button::Keyboard(Key::RCtrl)) = e.release_args() {
+            ctrl = false;
+        }
+        if e.press_args().is_some() {
+            // selections are not game events, but change the table all the same
+            changed.set(true);
+        }
+        let mut action = None;
+        // the summary of a deal that is over is left with its button, or enter
+        let mut go_on = false;
         if let Some(Button::Keyboard(key)) = e.press_args() {
-            game.keyboard(key);
-        }
-        if let Some(Button::Mouse(button)) = e.press_args() {
-            let mut sprite_ref = None;
-            for s in scene.children() {
-                let mx = mouse_pos[0];
-                let my = mouse_pos[1];
-                let [x, y, w, h] = s.bounding_box();
-                if mx >= x && mx <= x+w && my >= y && my <= y+h {
-                    sprite_ref = Some(SpriteRef::from(&s.id()));
+            if key == Key::LCtrl || key == Key::RCtrl {
+                ctrl = true;
+            } else if menu.is_open() {
+                action = menu.key(key);
+            } else if ctrl && (key == Key::Z || key == Key::Y) && replay.is_none() && thinking.is_none() {
+                let result = if key == Key::Z { game.undo() } else { game.redo() };
+                ui.interactions.clear();
+                if let Err(IntentError::IllegalAction(msg)) | Err(IntentError::PartialIntent(msg)) = result {
+                    ui.message = Some(msg.to_string());
+                }
+            } else if key == Key::A && game.player_turn.is_none() && replay.is_none() && analyses.is_empty() {
+                // where did each player lose points in the last round?
+                analyses = game.players.iter().map(|p| (p.name.clone(), Analysis::new(&game, p.id))).collect();
+                ui.message = Some("looking for costly plays".to_string());
+            } else if key == Key::R {
+                match replay.take() {
+                    Some((_, mut replay_ui)) => {
+                        ui.graphics_env = replay_ui.graphics_env.take();
+                    },
+                    None => {
+                        let replay_ui = Ui {
+                            graphics_env: ui.graphics_env.take(),
+                            viewer: Viewer::Spectator,
+                            reveal_all: true,
+                            ..Default::default()
+                        };
+                        replay_scene = Scene::new();
+                        replay = Some((Replay::new(&game), replay_ui));
+                    }
+                }
+            } else if let Some((r, replay_ui)) = replay.as_mut() {
+                let stepped = match key {
+                    Key::Left => r.backward(),
+                    Key::Right => r.forward(),
+                    _ => Ok(false)
+                };
+                match stepped {
+                    Ok(true) => {
+                        replay_scene = Scene::new();
+                        replay_ui.message = Some(format!("replay: {}/{}", r.position(), r.len()));
+                    },
+                    Ok(false) => {},
+                    Err(e) => replay_ui.message = Some(e),
                 }
+            } else if key == Key::Return && !game.summary().is_empty() {
+                go_on = true;
+            } else if key == Key::Escape && ui.interactions.is_empty() && ui.message.is_none() {
+                // escape cancels the selection first, and pauses the game once there is none
+                menu.open(Page::Pause);
+            } else {
+                ui.keyboard(&mut game, key);
+            }
+        }
+        if let Some(text) = e.text_args() {
+            if menu.is_open() {
+                menu.text(&text);
+            }
+        }
+        // cards are clicked, or dragged from the hand onto the table
+        if let Some(Button::Mouse(_button)) = e.press_args() {
+            if menu.is_open() {
+                action = menu.line_at(mouse_pos[1]).and_then(|line| menu.click(line));
+            } else if replay.is_none() && game.on_summary_button(window_size.width, window_size.height, mouse_pos) {
+                go_on = true;
+            } else if replay.is_none() {
+                ui.mouse_down(&mut game, &mut scene, mouse_pos);
+            }
+        }
+        if let Some(Button::Mouse(_button)) = e.release_args() {
+            if replay.is_none() && !menu.is_open() {
+                ui.mouse_up(&mut game, &mut scene, mouse_pos);
+                changed.set(true);
             }
-            game.click(sprite_ref);
         }
         e.mouse_cursor(|pos| {
             mouse_pos = pos;
         });
+        if e.mouse_cursor_args().is_some() && replay.is_none() && !menu.is_open() {
+            ui.mouse_moved(&game, &mut scene, mouse_pos);
+        }
+
+        if go_on {
+            if game.deal_pending() && client.is_none() {
+                // the cards of the last deal are gone from the table, and the hands are covered
+                game.next_deal();
+                ui.interactions.clear();
+                ui.revealed = None;
+                scene = Scene::new();
+                ui.prepare(&mut game, &mut scene);
+            } else {
+                menu.open(if client.is_some() { Page::Pause } else { Page::Main });
+            }
+        }
+
+        // a game is started, loaded or saved from the menus
+        let started = match action {
+            Some(Action::Start) | Some(Action::Restart) => Some(menu.setup.game()),
+            Some(Action::Load) => Some(std::fs::read_to_string(&save_path).map_err(|e| format!("could not load {}: {}", save_path, e))
+                .and_then(|text| Setup::load(&text))
+                .map(|(setup, game)| {
+                    menu.setup = setup;
+                    game
+                })),
+            Some(Action::Save) => {
+                menu.message = Some(match std::fs::write(&save_path, menu.setup.save(&game)) {
+                    Ok(()) => format!("saved to {}", save_path),
+                    Err(e) => format!("could not save to {}: {}", save_path, e),
+                });
+                None
+            },
+            Some(Action::Quit) => break,
+            None => None,
+        };
+        match started {
+            Some(Ok(started)) => {
+                let fresh = Ui {
+                    graphics_env: ui.graphics_env.take(),
+                    ..Default::default()
+                };
+                let (started, fresh) = open(started, fresh, &options, false, &changed, &told, &mut scene);
+                let old = std::mem::replace(&mut game, started);
+                old.discard();
+                ui = fresh;
+                // the computer's thoughts and the analysis were about the old game
+                thinking = None;
+                for (_, analysis) in analyses.drain(..) {
+                    analysis.mistakes();
+                }
+                menu.close();
+                idle = 0.0;
+            },
+            Some(Err(e)) => menu.message = Some(e),
+            None => {}
+        }
 
-        game.update(&window_size, &mut scene);
-        if let Some(args) = e.render_args() {
-            game.render(&mut scene, &args);
+        if menu.is_open() {
+            if let Some(args) = e.render_args() {
+                ui.graphics_env.as_mut().unwrap().render_menu(&menu, &args);
+            }
+            continue;
+        }
+        match replay.as_mut() {
+            Some((r, replay_ui)) => {
+                replay_ui.update(&mut r.game, &window_size, &mut replay_scene);
+                if let Some(args) = e.render_args() {
+                    replay_ui.render(&r.game, &mut replay_scene, &args);
+                }
+            },
+            None => {
+                if changed.replace(false) {
+                    ui.update(&mut game, &window_size, &mut scene);
+                }
+                if let Some(args) = e.render_args() {
+                    ui.render(&game, &mut scene, &args);
+                }
+            }
         }
     }
 
@@ -156,3 +458,56 @@ fn main() {
         }
     }*/
 }
+
+/// Tells what was wrong with the command line and how to give it, and exits.
+fn usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("usage: rcards [--ai <seat>[:<controller>]]... [--serve <address> [--takeover <seconds>[:<controller>]]]");
+    eprintln!("              [--connect <address> [--seat <seat> | --token <token> | --watch]] [--open-hands]");
+    eprintln!("              [--no-undo] [--animation <seconds>] [--save <path>] [--cards <pack>] [--back <name>] [--verbose]");
+    std::process::exit(2);
+}
+
+/// Readies `game` and the window showing it, and starts the game unless it has been started
+/// already, as a game hosted elsewhere or loaded from a save has. The scene is started over for
+/// its cards.
+fn open(mut game: Game, mut ui: Ui, options: &Options, hosted: bool, changed: &Rc<Cell<bool>>, told: &Rc<Cell<Option<String>>>, scene: &mut Scene<Texture>) -> (Game, Ui) {
+    if options.watch && !hosted {
+        ui.viewer = Viewer::Spectator;
+        ui.reveal_all = options.open_hands;
+    }
+    // the host can rule out taking back moves, e.g. in competitive games
+    game.undo_enabled = !hosted && options.undo;
+    // a single player against the computer is shown their own hand, and not the computer's
+    let humans: Vec<u8> = (0..game.players.len() as u8).filter(|s| game.players[*s as usize].controller.is_none()).collect();
+    if let (Viewer::InTurn, [seat]) = (ui.viewer, &humans[..]) {
+        ui.viewer = Viewer::Seat(*seat);
+    }
+    // players sharing the window pass it on between turns, unless they play open
+    let humans = humans.len();
+    ui.privacy_screen = ui.viewer == Viewer::InTurn && humans > 1 && !ui.reveal_all;
+    if let Some(time) = options.animation {
+        ui.animation_time = time;
+    }
+
+    {
+        let changed = changed.clone();
+        game.subscribe(move |_: &Event| changed.set(true));
+    }
+    {
+        let told = told.clone();
+        game.subscribe(move |e: &Event| if let Event::IllegalPlay(_) = e {
+            told.set(Some(e.to_string()));
+        });
+    }
+    if options.verbose {
+        game.subscribe(|e: &Event| println!("{}", e));
+    }
+    if !hosted && game.round == 0 {
+        game.start();
+    }
+    *scene = Scene::new();
+    ui.prepare(&mut game, scene);
+    changed.set(true);
+    (game, ui)
+}
@@ -0,0 +1,286 @@
+//! The menus of the window: the main menu it opens with, the setup of a new game and the pause
+//! menu over a game in progress. A menu is a list of lines, chosen with the arrow keys and enter
+//! or by clicking them. The options of the setup are changed with left and right or by clicking,
+//! and names and the seed are typed over the line.
+
+use crate::kasino::Variant;
+use crate::setup::{Setup, CONTROLLERS};
+use piston::Key;
+
+/// Where the first line of a menu is drawn, and how far apart the lines are.
+pub const TOP: f64 = 140.0;
+pub const LINE_HEIGHT: f64 = 36.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Page {
+    Main,
+    Setup,
+    Pause,
+}
+
+/// What the window is to do for a choice made in a menu.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// Starts a game as set up.
+    Start,
+    Save,
+    Load,
+    /// Starts the game being played over, as it was set up.
+    Restart,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Item {
+    NewGame,
+    Load,
+    Quit,
+    Resume,
+    Save,
+    Restart,
+    Seats,
+    Seat(usize),
+    Variant,
+    Target,
+    Seed,
+    Start,
+    Back,
+}
+
+pub struct Menu {
+    /// The menu shown, or none while a game is played.
+    pub page: Option<Page>,
+    pub setup: Setup,
+    /// The game is hosted elsewhere, so it cannot be saved, loaded or restarted here.
+    pub hosted: bool,
+    /// What the window last has to say, e.g. why a game could not be loaded.
+    pub message: Option<String>,
+    cursor: usize,
+}
+
+impl Menu {
+    pub fn new(setup: Setup) -> Menu {
+        Menu {
+            page: Some(Page::Main),
+            setup,
+            hosted: false,
+            message: None,
+            cursor: 0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.page.is_some()
+    }
+
+    pub fn open(&mut self, page: Page) {
+        self.page = Some(page);
+        self.cursor = 0;
+        self.message = None;
+    }
+
+    pub fn close(&mut self) {
+        self.page = None;
+        self.message = None;
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self.page {
+            Some(Page::Main) | None => "kasino",
+            Some(Page::Setup) => "new game",
+            Some(Page::Pause) => "paused",
+        }
+    }
+
+    fn items(&self) -> Vec<Item> {
+        match self.page {
+            Some(Page::Main) => vec!(Item::NewGame, Item::Load, Item::Quit),
+            Some(Page::Setup) => {
+                let mut items = vec!(Item::Seats);
+                items.extend((0..self.setup.seats.len()).map(Item::Seat));
+                items.extend(vec!(Item::Variant, Item::Target, Item::Seed, Item::Start, Item::Back));
+                items
+            },
+            Some(Page::Pause) if self.hosted => vec!(Item::Resume, Item::Quit),
+            Some(Page::Pause) => vec!(Item::Resume, Item::Save, Item::Load, Item::Restart, Item::Quit),
+            None => vec!(),
+        }
+    }
+
+    /// The lines of the menu shown.
+    pub fn lines(&self) -> Vec<String> {
+        self.items().into_iter().map(|item| match item {
+            Item::NewGame => "new game".to_string(),
+            Item::Load => "load".to_string(),
+            Item::Quit => "quit".to_string(),
+            Item::Resume => "resume".to_string(),
+            Item::Save => "save".to_string(),
+            Item::Restart => "restart".to_string(),
+            Item::Seats => format!("seats: {}", self.setup.seats.len()),
+            Item::Seat(i) => {
+                let seat = &self.setup.seats[i];
+                format!("seat {}: {} ({})", i + 1, seat.name, seat.ai.as_deref().unwrap_or("human"))
+            },
+            Item::Variant => format!("rules: {}", self.setup.variant),
+            Item::Target if self.setup.target == 0 => "play: a single deal".to_string(),
+            Item::Target => format!("play to: {} points", self.setup.target),
+            Item::Seed => format!("seed: {}", self.setup.seed.map(|s| s.to_string()).unwrap_or("random".to_string())),
+            Item::Start => "start".to_string(),
+            Item::Back => "back".to_string(),
+        }).collect()
+    }
+
+    /// The line under the cursor.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The line at height `y` in the window.
+    pub fn line_at(&self, y: f64) -> Option<usize> {
+        let line = ((y - TOP) / LINE_HEIGHT + 0.5).floor();
+        if line >= 0.0 && (line as usize) < self.items().len() {
+            Some(line as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn key(&mut self, key: Key) -> Option<Action> {
+        let lines = self.items().len();
+        if lines == 0 {
+            return None;
+        }
+        match key {
+            Key::Up => self.cursor = (self.cursor + lines - 1) % lines,
+            Key::Down => self.cursor = (self.cursor + 1) % lines,
+            Key::Left => self.change(-1),
+            Key::Right => self.change(1),
+            Key::Return => return self.choose(),
+            Key::Backspace => self.erase(),
+            Key::Escape => match self.page {
+                Some(Page::Setup) => self.open(Page::Main),
+                Some(Page::Pause) => self.close(),
+                _ => {}
+            },
+            _ => {}
+        }
+        None
+    }
+
+    /// Chooses the line clicked.
+    pub fn click(&mut self, line: usize) -> Option<Action> {
+        if line >= self.items().len() {
+            return None;
+        }
+        self.cursor = line;
+        self.choose()
+    }
+
+    /// Types `text` into the name or the seed under the cursor.
+    pub fn text(&mut self, text: &str) {
+        match self.items().get(self.cursor) {
+            Some(Item::Seat(i)) => self.setup.seats[*i].name.extend(text.chars().filter(|c| !c.is_control())),
+            Some(Item::Seed) => {
+                let digits: String = self.setup.seed.map(|s| s.to_string()).unwrap_or_default()
+                    + &text.chars().filter(|c| c.is_ascii_digit()).collect::<String>();
+                self.setup.seed = digits.parse().ok().or(self.setup.seed);
+            },
+            _ => {}
+        }
+    }
+
+    fn erase(&mut self) {
+        match self.items().get(self.cursor) {
+            Some(Item::Seat(i)) => {
+                self.setup.seats[*i].name.pop();
+            },
+            Some(Item::Seed) => {
+                let mut digits = self.setup.seed.map(|s| s.to_string()).unwrap_or_default();
+                digits.pop();
+                self.setup.seed = digits.parse().ok();
+            },
+            _ => {}
+        }
+    }
+
+    fn choose(&mut self) -> Option<Action> {
+        match self.items().get(self.cursor)? {
+            Item::NewGame => self.open(Page::Setup),
+            Item::Load => return Some(Action::Load),
+            Item::Quit => return Some(Action::Quit),
+            Item::Resume => self.close(),
+            Item::Save => return Some(Action::Save),
+            Item::Restart => return Some(Action::Restart),
+            Item::Start => return Some(Action::Start),
+            Item::Back => self.open(Page::Main),
+            _ => self.change(1),
+        }
+        None
+    }
+
+    /// Steps the option under the cursor `step` choices on.
+    fn change(&mut self, step: isize) {
+        let cycle = |i: usize, n: usize| (i as isize + step).rem_euclid(n as isize) as usize;
+        match self.items().get(self.cursor) {
+            Some(Item::Seats) => {
+                let seats = self.setup.seats.len() as isize + step;
+                self.setup.set_seats(seats.max(0) as usize);
+            },
+            Some(Item::Seat(i)) => {
+                let seat = &mut self.setup.seats[*i];
+                let current = CONTROLLERS.iter().position(|c| Some(*c) == seat.ai.as_deref()).unwrap_or(0);
+                seat.ai = match cycle(current, CONTROLLERS.len()) {
+                    0 => None,
+                    next => Some(CONTROLLERS[next].to_string()),
+                };
+            },
+            Some(Item::Variant) => {
+                let current = Variant::ALL.iter().position(|v| *v == self.setup.variant).unwrap();
+                self.setup.variant = Variant::ALL[cycle(current, Variant::ALL.len())];
+            },
+            Some(Item::Target) => {
+                // a single deal, or a match to 5 points and up
+                self.setup.target = match (self.setup.target as isize + step) as u8 {
+                    1..=4 if step > 0 => 5,
+                    1..=4 => 0,
+                    target => target.min(99),
+                };
+            },
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_up_a_game() {
+        let mut menu = Menu::new(Setup::default());
+        assert_eq!(menu.lines(), vec!("new game", "load", "quit"));
+        assert_eq!(menu.key(Key::Return), None);
+        assert_eq!(menu.page, Some(Page::Setup));
+
+        menu.key(Key::Right);
+        assert_eq!(menu.lines()[0], "seats: 3");
+        menu.key(Key::Down);
+        menu.key(Key::Down);
+        menu.key(Key::Left);
+        menu.key(Key::Backspace);
+        menu.text("two");
+        assert_eq!(menu.lines()[2], "seat 2: playertwo (expert)");
+        assert_eq!(menu.click(5), None);
+        assert_eq!(menu.lines()[5], "play to: 5 points");
+        menu.key(Key::Down);
+        menu.text("42");
+        assert_eq!(menu.setup.seed, Some(42));
+        assert_eq!(menu.click(menu.line_at(TOP + 7.0 * LINE_HEIGHT).unwrap()), Some(Action::Start));
+
+        menu.open(Page::Pause);
+        menu.hosted = true;
+        assert_eq!(menu.lines(), vec!("resume", "quit"));
+        menu.key(Key::Escape);
+        assert!(!menu.is_open());
+    }
+}
@@ -0,0 +1,684 @@
+//! Plays a game between machines on a network. The server owns the game and is the only one to
+//! play intents on it; clients send the plays made at their window and get back what their seat
+//! may see, which they show in a game of their own that mirrors the server's.
+//!
+//! Server and client talk in lines of text, with cards and moves written as in the bot protocol.
+//!
+//! ```text
+//! client: join [<seat>]                 takes the given seat, or the first free one
+//!       | rejoin <token>                takes back the seat the token was given for
+//!       | watch                         watches without a seat
+//! server: welcome <seat> <players> <token> | welcome - <players>
+//! server: event <event>                 every event since the last state, as the client may see it
+//! server: state                         after joining and after every play
+//!         <view>                        the client's view, as in the bot protocol
+//!         end
+//! client: play <move>
+//! server: error <reason>                when a line from the client could not be followed
+//! server: away <seat> | back <seat>     when a player loses the connection, and rejoins
+//! ```
+//!
+//! Events are written as `dealt 0 S:2`, `trailed 0 S:2`, `captured 0 S:2 D:2`, `built 0 S:2 9`,
+//! `cleared 0 H:3 C:9`, `swept 0`, `turn 1`, `round 2`, `over`, `undone trail S:2` and
+//! `illegal 1`, with `??` for a card the client may not see. Spectators see no hand, unless the
+//! server has opened the hands to them.
+//!
+//! A seat whose player drops is kept for them: the game waits at the seat's turn until they
+//! rejoin with the token they were welcomed with, or, if the server is set to, until the computer
+//! takes the seat over.
+
+use crate::types::{Game, Intent, Player, Viewer};
+use crate::graphic::Ui;
+use crate::events::Event;
+use crate::view::PlayerView;
+use crate::ai;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::{Uuid, UuidVersion};
+use sprite::Scene;
+use opengl_graphics::Texture;
+
+enum Message {
+    Connected(usize, TcpStream),
+    Line(usize, String),
+    Closed(usize),
+}
+
+/// Hosts a game. Seats without a controller are played by clients, and the game waits until all
+/// of them are taken. Any number of spectators may watch.
+pub struct Server {
+    game: Game,
+    address: SocketAddr,
+    messages: Receiver<Message>,
+    connections: HashMap<usize, TcpStream>,
+    seats: Vec<Seat>,
+    spectators: Vec<usize>,
+    events: Rc<RefCell<Vec<Event>>>,
+    started: bool,
+    /// Shows spectators every hand, e.g. when teaching.
+    pub open_hands: bool,
+    /// How long a seat whose player has dropped waits for them before the computer plays it.
+    /// `None` waits for as long as it takes.
+    pub takeover: Option<Duration>,
+    /// The controller that plays a seat taken over, as given to `ai::from_spec`.
+    pub stand_in: String,
+}
+
+/// A seat played by a client. The seat is kept for its player when they drop, and given back to
+/// whoever rejoins with its token.
+#[derive(Default)]
+struct Seat {
+    client: Option<usize>,
+    token: Option<String>,
+    dropped: Option<Instant>,
+    /// Whether the computer plays the seat while its player is away.
+    covered: bool,
+}
+
+impl Server {
+    /// Listens on `address` for clients of `game`, which is started if it has not been.
+    pub fn bind(address: &str, mut game: Game) -> io::Result<Server> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for (id, stream) in listener.incoming().enumerate() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue
+                };
+                let reader = match stream.try_clone() {
+                    Ok(reader) => reader,
+                    Err(_) => continue
+                };
+                if sender.send(Message::Connected(id, stream)).is_err() {
+                    break;
+                }
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    for line in BufReader::new(reader).lines() {
+                        match line {
+                            Ok(line) => if sender.send(Message::Line(id, line)).is_err() {
+                                return;
+                            },
+                            Err(_) => break
+                        }
+                    }
+                    let _ = sender.send(Message::Closed(id));
+                });
+            }
+        });
+
+        let events = Rc::new(RefCell::new(vec!()));
+        {
+            let events = events.clone();
+            game.subscribe(move |e: &Event| events.borrow_mut().push(e.clone()));
+        }
+        if game.round == 0 {
+            game.start();
+        }
+        // clients are sent the state they join at, not how it came about
+        events.borrow_mut().clear();
+        let seats = game.players.iter().map(|_| Seat::default()).collect();
+        Ok(Server {
+            game,
+            address,
+            messages,
+            connections: HashMap::new(),
+            seats,
+            spectators: vec!(),
+            events,
+            started: false,
+            open_hands: false,
+            takeover: None,
+            stand_in: "medium".to_string(),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Serves the game until it is over, dealing again until the match is won. The game begins
+    /// once every seat has been taken, and only waits for seats whose player has dropped after
+    /// that.
+    pub fn run(&mut self) {
+        'deals: loop {
+            while self.game.player_turn.is_some() {
+                match self.messages.recv_timeout(Duration::from_millis(100)) {
+                    Ok(message) => self.handle(message),
+                    Err(RecvTimeoutError::Timeout) => {},
+                    Err(RecvTimeoutError::Disconnected) => break 'deals,
+                }
+                self.take_over();
+                self.started = self.started || self.seated();
+                if self.started && self.game.advance() {
+                    self.broadcast();
+                }
+            }
+            if !self.game.deal_pending() {
+                break;
+            }
+            // clients see the deal end before the next one is dealt
+            self.broadcast();
+            self.game.next_deal();
+        }
+        self.broadcast();
+    }
+
+    /// Whether every seat is either taken by a client or played by a controller.
+    fn seated(&self) -> bool {
+        self.game.players.iter().zip(&self.seats).all(|(p, seat)| p.controller.is_some() || seat.client.is_some())
+    }
+
+    /// Lets the computer play the seats whose players have been away for too long.
+    fn take_over(&mut self) {
+        let takeover = match self.takeover {
+            Some(takeover) => takeover,
+            None => return
+        };
+        for (i, seat) in self.seats.iter_mut().enumerate() {
+            if seat.covered || seat.dropped.is_none_or(|dropped| dropped.elapsed() < takeover) {
+                continue;
+            }
+            match ai::from_spec(&self.stand_in) {
+                Ok(controller) => {
+                    self.game.players[i].controller = Some(controller);
+                    seat.covered = true;
+                },
+                // the stand-in is checked when the server is set up, so this is not expected
+                Err(e) => eprintln!("could not take over seat {}: {}", i, e),
+            }
+        }
+    }
+
+    fn handle(&mut self, message: Message) {
+        match message {
+            Message::Connected(id, stream) => {
+                self.connections.insert(id, stream);
+            },
+            Message::Closed(id) => {
+                self.connections.remove(&id);
+                self.spectators.retain(|s| *s != id);
+                if let Some(seat) = self.seats.iter().position(|s| s.client == Some(id)) {
+                    self.seats[seat].client = None;
+                    self.seats[seat].dropped = Some(Instant::now());
+                    self.tell_all(&format!("away {}", seat));
+                }
+            },
+            Message::Line(id, line) => {
+                if let Err(reason) = self.follow(id, &line) {
+                    self.send(id, &[format!("error {}", reason)]);
+                }
+            }
+        }
+    }
+
+    fn follow(&mut self, id: usize, line: &str) -> Result<(), String> {
+        let mut parts = line.trim().splitn(2, ' ');
+        let joined = self.seats.iter().any(|s| s.client == Some(id)) || self.spectators.contains(&id);
+        match (parts.next().unwrap(), parts.next()) {
+            ("join", _) | ("rejoin", _) | ("watch", _) if joined => Err("you have already joined".to_string()),
+            ("join", seat) => {
+                let free = |s: usize| self.seats[s].token.is_none() && self.game.players[s].controller.is_none();
+                let seat = match seat {
+                    Some(seat) => seat.trim().parse::<usize>().ok()
+                        .filter(|s| *s < self.seats.len())
+                        .ok_or(format!("there is no seat '{}'", seat))?,
+                    None => (0..self.seats.len()).find(|s| free(*s)).ok_or("all seats are taken")?,
+                };
+                if !free(seat) {
+                    return Err(format!("seat {} is taken", seat));
+                }
+                self.seats[seat].token = Some(Uuid::new(UuidVersion::Random).unwrap().to_string());
+                self.seat(id, seat);
+                Ok(())
+            },
+            ("rejoin", Some(token)) => {
+                let seat = self.seats.iter().position(|s| s.token.as_deref() == Some(token.trim()))
+                    .ok_or("there is no seat with that token")?;
+                // a connection that has not been noticed to drop yet is replaced
+                if let Some(old) = self.seats[seat].client {
+                    self.connections.remove(&old);
+                }
+                if self.seats[seat].covered {
+                    self.game.players[seat].controller = None;
+                    self.seats[seat].covered = false;
+                }
+                self.seats[seat].dropped = None;
+                self.seat(id, seat);
+                self.tell_all(&format!("back {}", seat));
+                Ok(())
+            },
+            ("watch", None) => {
+                self.spectators.push(id);
+                let mut lines = vec!(format!("welcome - {}", self.seats.len()));
+                lines.append(&mut state(&self.game, None, self.open_hands));
+                self.send(id, &lines);
+                Ok(())
+            },
+            ("play", Some(intent)) => {
+                let seat = self.seats.iter().position(|s| s.client == Some(id)).ok_or("join a seat first")?;
+                if !self.started {
+                    return Err("the game starts once every seat is taken".to_string());
+                }
+                if self.game.player_turn != Some(seat as u8) {
+                    return Err("it is not your turn".to_string());
+                }
+                let intent: Intent = intent.parse()?;
+                self.game.play(intent).map_err(|e| format!("{:?}", e))?;
+                self.broadcast();
+                Ok(())
+            },
+            _ => Err(format!("unknown command '{}'", line)),
+        }
+    }
+
+    /// Gives `seat` to the client `id` and sends it all it needs to play the seat.
+    fn seat(&mut self, id: usize, seat: usize) {
+        self.seats[seat].client = Some(id);
+        let token = self.seats[seat].token.clone().unwrap_or_default();
+        let mut lines = vec!(format!("welcome {} {} {}", seat, self.seats.len(), token));
+        lines.append(&mut state(&self.game, Some(seat as u8), false));
+        self.send(id, &lines);
+    }
+
+    fn send(&mut self, id: usize, lines: &[String]) {
+        if let Some(stream) = self.connections.get_mut(&id) {
+            let _ = stream.write_all(lines.iter().map(|l| format!("{}\n", l)).collect::<String>().as_bytes());
+        }
+    }
+
+    /// Sends `line` to every client.
+    fn tell_all(&mut self, line: &str) {
+        let ids: Vec<usize> = self.connections.keys().copied().collect();
+        for id in ids {
+            self.send(id, &[line.to_string()]);
+        }
+    }
+
+    /// Sends every client the events since the last broadcast and the state, as it may see them.
+    fn broadcast(&mut self) {
+        let events: Vec<Event> = self.events.borrow_mut().drain(..).collect();
+        let mut clients: Vec<(usize, Option<u8>)> = self.seats.iter().enumerate()
+            .filter_map(|(seat, s)| s.client.map(|id| (id, Some(seat as u8))))
+            .collect();
+        clients.extend(self.spectators.iter().map(|id| (*id, None)));
+        for (id, seat) in clients {
+            let open = seat.is_none() && self.open_hands;
+            let mut lines: Vec<String> = events.iter()
+                .map(|e| format!("event {}", if open { e.clone() } else { e.seen_by(seat) }.to_line()))
+                .collect();
+            lines.append(&mut state(&self.game, seat, self.open_hands));
+            self.send(id, &lines);
+        }
+    }
+}
+
+/// Plays a seat of a game hosted by a server, or watches it. A client that loses the connection
+/// tries to rejoin its seat every few seconds.
+pub struct Client {
+    pub seat: Option<u8>,
+    /// Gets the seat back after a drop, also from another client.
+    pub token: Option<String>,
+    address: String,
+    stream: TcpStream,
+    lines: Receiver<String>,
+    state: Option<Vec<String>>,
+    submit: Sender<Intent>,
+    intents: Receiver<Intent>,
+    retried: Instant,
+}
+
+impl Client {
+    /// Joins the game at `address`, at `seat` if given, and returns a game to mirror it in.
+    pub fn connect(address: &str, seat: Option<u8>) -> io::Result<(Client, Game)> {
+        match seat {
+            Some(seat) => Self::open(address, &format!("join {}", seat)),
+            None => Self::open(address, "join"),
+        }
+    }
+
+    /// Takes back the seat that `token` was given for.
+    pub fn rejoin(address: &str, token: &str) -> io::Result<(Client, Game)> {
+        Self::open(address, &format!("rejoin {}", token))
+    }
+
+    /// Watches the game at `address` without a seat, and returns a game to mirror it in.
+    pub fn watch(address: &str) -> io::Result<(Client, Game)> {
+        Self::open(address, "watch")
+    }
+
+    fn open(address: &str, join: &str) -> io::Result<(Client, Game)> {
+        let (stream, lines) = dial(address, join)?;
+        let welcome = lines.recv_timeout(Duration::from_secs(5))
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "the server did not answer"))?;
+        let words: Vec<&str> = welcome.split_whitespace().collect();
+        let (seat, players, token) = match words.as_slice() {
+            ["welcome", seat, players, rest @ ..] => (
+                seat.parse::<u8>().ok(),
+                players.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, welcome.clone()))?,
+                rest.first().map(|t| t.to_string()),
+            ),
+            _ => return Err(io::Error::other(welcome)),
+        };
+
+        let (submit, intents) = mpsc::channel();
+        let game = Game {
+            players: (0..players).map(|i| Player::new(i, &format!("player{}", i + 1))).collect(),
+            undo_enabled: false,
+            ..Default::default()
+        };
+        let client = Client {
+            seat,
+            token,
+            address: address.to_string(),
+            stream,
+            lines,
+            state: None,
+            submit,
+            intents,
+            retried: Instant::now(),
+        };
+        Ok((client, game))
+    }
+
+    /// A window onto the mirrored game, shown from the client's seat. Spectators are shown every
+    /// hand they may see, and plays made at the window are sent to the server.
+    pub fn ui(&self) -> Ui {
+        Ui {
+            viewer: self.seat.map(Viewer::Seat).unwrap_or(Viewer::Spectator),
+            reveal_all: self.seat.is_none(),
+            submit: Some(self.submit.clone()),
+            ..Default::default()
+        }
+    }
+
+    /// Connects again after the connection was lost, at most every few seconds.
+    fn reconnect(&mut self) -> io::Result<()> {
+        if self.retried.elapsed() < Duration::from_secs(3) {
+            return Ok(());
+        }
+        self.retried = Instant::now();
+        let join = match &self.token {
+            Some(token) => format!("rejoin {}", token),
+            None => "watch".to_string(),
+        };
+        let (stream, lines) = dial(&self.address, &join)?;
+        self.stream = stream;
+        self.lines = lines;
+        self.state = None;
+        Ok(())
+    }
+
+    /// Sends the plays made at the window to the server, and mirrors the latest state from the
+    /// server in `game`. What the server has to say, and the points once the game is over, are
+    /// shown in the window. Returns whether `game` has changed.
+    pub fn sync(&mut self, game: &mut Game, ui: &mut Ui, scene: &mut Scene<Texture>) -> bool {
+        for intent in self.intents.try_iter() {
+            let _ = writeln!(self.stream, "play {}", intent);
+        }
+        let mut changed = false;
+        loop {
+            let line = match self.lines.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if let Err(e) = self.reconnect() {
+                        ui.message = Some(format!("could not reach the server: {}", e));
+                    }
+                    break;
+                }
+            };
+            if line == "state" {
+                self.state = Some(vec!());
+            } else if line == "end" {
+                if let Some(state) = self.state.take() {
+                    match PlayerView::from_lines(&state) {
+                        Ok(view) => {
+                            ui.replace_cards(game, scene, |game| view.apply(game));
+                            changed = true;
+                            if view.turn.is_none() {
+                                let points: Vec<String> = game.players.iter().zip(game.points())
+                                    .map(|(p, points)| format!("{}: {} points", p.name, points.total()))
+                                    .collect();
+                                ui.message = Some(points.join(", "));
+                            }
+                        },
+                        Err(e) => ui.message = Some(format!("could not follow the server: {}", e)),
+                    }
+                }
+            } else if let Some(state) = self.state.as_mut() {
+                state.push(line);
+            } else if let Some(event) = line.strip_prefix("event ") {
+                match Event::from_line(event) {
+                    Ok(event) => game.emit(event),
+                    Err(e) => ui.message = Some(format!("could not follow the server: {}", e)),
+                }
+            } else if let Some(seat) = line.strip_prefix("away ") {
+                ui.message = Some(format!("seat {} has lost its connection", seat));
+            } else if let Some(seat) = line.strip_prefix("back ") {
+                ui.message = Some(format!("seat {} is back", seat));
+            } else if let Some(reason) = line.strip_prefix("error ") {
+                ui.message = Some(format!("server: {}", reason));
+            }
+        }
+        changed
+    }
+}
+
+/// Connects to `address`, sends `join` and returns the stream and the lines read from it.
+fn dial(address: &str, join: &str) -> io::Result<(TcpStream, Receiver<String>)> {
+    let mut stream = TcpStream::connect(address)?;
+    let reader = stream.try_clone()?;
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            match line {
+                Ok(line) => if sender.send(line).is_err() {
+                    break;
+                },
+                Err(_) => break
+            }
+        }
+    });
+    writeln!(stream, "{}", join)?;
+    Ok((stream, lines))
+}
+
+/// The state message for `seat`, or for a spectator.
+fn state(game: &Game, seat: Option<u8>, open_hands: bool) -> Vec<String> {
+    let view = match seat {
+        Some(seat) => game.view(seat),
+        None => game.spectator_view(open_hands),
+    };
+    let mut lines = vec!("state".to_string());
+    lines.append(&mut view.to_lines());
+    lines.push("end".to_string());
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Player;
+
+    fn game() -> Game {
+        Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mirror_shows_only_own_hand() {
+        let mut game = game();
+        game.start();
+        let mut mirror = Game {
+            players: vec!(Player::new(0, "?"), Player::new(1, "?")),
+            ..Default::default()
+        };
+        let lines = state(&game, Some(1), false);
+        PlayerView::from_lines(&lines[1..lines.len() - 1]).unwrap().apply(&mut mirror);
+        assert_eq!(mirror.players[1].hand.cards(), game.players[1].hand.cards());
+        assert!(mirror.players[0].hand.cards().iter().all(crate::types::Card::is_hidden));
+        assert_eq!(mirror.players[0].hand.len(), 4);
+        assert_eq!(mirror.deck.len(), game.deck.len());
+        assert_eq!(mirror.table.len(), 4);
+        assert_eq!(mirror.table.get(0).unwrap().cards(), game.table.get(0).unwrap().cards());
+        assert_eq!(mirror.player_turn, Some(0));
+        assert_eq!(mirror.players[0].name, "player1");
+    }
+
+    /// Serves `game` on a port of its own, in a thread, as the game cannot be moved into one.
+    fn serve<F: FnOnce() -> Game + Send + 'static>(game: F, takeover: Option<Duration>) -> SocketAddr {
+        let (sender, address) = mpsc::channel();
+        thread::spawn(move || {
+            let mut server = Server::bind("127.0.0.1:0", game()).unwrap();
+            server.takeover = takeover;
+            sender.send(server.local_addr()).unwrap();
+            server.run();
+        });
+        address.recv().unwrap()
+    }
+
+    #[test]
+    fn test_server_plays_the_whole_match() {
+        let mut game = Game {
+            target: 11,
+            ..game()
+        };
+        for p in &mut game.players {
+            p.controller = Some(ai::from_spec("easy").unwrap());
+        }
+        let mut server = Server::bind("127.0.0.1:0", game).unwrap();
+        server.run();
+        assert!(server.game.match_winner().is_some());
+        assert!(!server.game.deals.is_empty());
+    }
+
+    fn connect(address: SocketAddr) -> (TcpStream, io::Lines<BufReader<TcpStream>>) {
+        let stream = TcpStream::connect(address).unwrap();
+        let lines = BufReader::new(stream.try_clone().unwrap()).lines();
+        (stream, lines)
+    }
+
+    /// Reads up to the end of the next state, or an error.
+    fn read_state(lines: &mut io::Lines<BufReader<TcpStream>>) -> Vec<String> {
+        let mut state = vec!();
+        for line in lines {
+            let line = line.unwrap();
+            if line.starts_with("error") {
+                return vec!(line);
+            }
+            let end = line == "end";
+            state.push(line);
+            if end {
+                break;
+            }
+        }
+        state
+    }
+
+    #[test]
+    fn test_server_validates_plays() {
+        let address = serve(game, None);
+        let (mut first, mut first_lines) = connect(address);
+        let (mut second, mut second_lines) = connect(address);
+
+        writeln!(first, "join").unwrap();
+        let state = read_state(&mut first_lines);
+        assert!(state[0].starts_with("welcome 0 2 "));
+        let hand = state.iter().find(|l| l.starts_with("hand ")).unwrap();
+        let card = hand.split_whitespace().nth(1).unwrap();
+        writeln!(first, "play trail {}", card).unwrap();
+        assert_eq!(read_state(&mut first_lines), vec!("error the game starts once every seat is taken"));
+        writeln!(second, "join 0").unwrap();
+        assert_eq!(read_state(&mut second_lines), vec!("error seat 0 is taken"));
+        writeln!(second, "join").unwrap();
+        assert!(read_state(&mut second_lines)[0].starts_with("welcome 1 2 "));
+
+        writeln!(second, "play trail C:5").unwrap();
+        assert_eq!(read_state(&mut second_lines), vec!("error it is not your turn"));
+
+        writeln!(first, "play trail {}", card).unwrap();
+        let state = read_state(&mut second_lines);
+        assert!(state.contains(&format!("event trailed 0 {}", card)));
+        assert!(state.contains(&"turn 1".to_string()));
+        assert!(state.contains(&"player 0 3 0 player1".to_string()));
+    }
+
+    #[test]
+    fn test_spectator_joins_mid_game() {
+        let address = serve(game, None);
+        let (mut first, mut first_lines) = connect(address);
+        let (mut second, mut second_lines) = connect(address);
+        writeln!(first, "join").unwrap();
+        let state = read_state(&mut first_lines);
+        writeln!(second, "join").unwrap();
+        read_state(&mut second_lines);
+        let hand = state.iter().find(|l| l.starts_with("hand ")).unwrap();
+        writeln!(first, "play trail {}", hand.split_whitespace().nth(1).unwrap()).unwrap();
+        read_state(&mut first_lines);
+
+        let (mut spectator, mut spectator_lines) = connect(address);
+        writeln!(spectator, "watch").unwrap();
+        let state = read_state(&mut spectator_lines);
+        assert_eq!(state[0], "welcome - 2");
+        assert!(state.contains(&"seat -".to_string()));
+        assert!(state.contains(&"hand".to_string()));
+        assert!(state.contains(&"player 0 3 0 player1".to_string()));
+        assert!(!state.iter().any(|l| l.starts_with("shown")));
+        writeln!(spectator, "play trail C:5").unwrap();
+        assert_eq!(read_state(&mut spectator_lines), vec!("error join a seat first"));
+
+        // spectators follow the same events as the players
+        let hand = read_state(&mut second_lines).into_iter().find(|l| l.starts_with("hand ")).unwrap();
+        let card = hand.split_whitespace().nth(1).unwrap().to_string();
+        writeln!(second, "play trail {}", card).unwrap();
+        let state = read_state(&mut spectator_lines);
+        assert_eq!(state[0], format!("event trailed 1 {}", card));
+        assert_eq!(state[1], "event turn 0");
+    }
+
+    #[test]
+    fn test_rejoin_after_takeover() {
+        let address = serve(game, Some(Duration::from_millis(100)));
+        let (mut first, mut first_lines) = connect(address);
+        let (mut second, mut second_lines) = connect(address);
+        writeln!(first, "join").unwrap();
+        let state = read_state(&mut first_lines);
+        writeln!(second, "join").unwrap();
+        let welcome = read_state(&mut second_lines)[0].clone();
+        let token = welcome.split_whitespace().nth(3).unwrap().to_string();
+        let hand = state.iter().find(|l| l.starts_with("hand ")).unwrap();
+        writeln!(first, "play trail {}", hand.split_whitespace().nth(1).unwrap()).unwrap();
+        read_state(&mut first_lines);
+
+        // the second player drops in turn, and the computer plays for them
+        second.shutdown(std::net::Shutdown::Both).unwrap();
+        drop(second_lines);
+        let state = read_state(&mut first_lines);
+        assert_eq!(state[0], "away 1");
+        assert!(state.contains(&"turn 0".to_string()));
+        // what the computer played for them, as broadcast, which may have swept the table
+        let played = state.iter().find(|l| l.starts_with("player 1 ")).unwrap().clone();
+        assert!(played.starts_with("player 1 3 "));
+
+        let (mut third, mut third_lines) = connect(address);
+        writeln!(third, "rejoin nonsense").unwrap();
+        assert_eq!(read_state(&mut third_lines), vec!("error there is no seat with that token"));
+        writeln!(third, "rejoin {}", token).unwrap();
+        let state = read_state(&mut third_lines);
+        assert_eq!(state[0], welcome);
+        assert!(state.contains(&played));
+        assert_eq!(first_lines.next().unwrap().unwrap(), "back 1");
+    }
+}
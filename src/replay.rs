@@ -0,0 +1,182 @@
+use crate::types::{Game, Player, Deck, Card, Intent, LogEntry};
+use crate::kasino::{IntentError, Variant};
+
+/// Steps through a recorded game by re-applying its plays to a fresh game dealt from the same
+/// deck order.
+pub struct Replay {
+    recording: Recording,
+    position: usize,
+    pub game: Game,
+}
+
+/// What a game is replayed from: its players, deck order and plays, and the rules and match it
+/// was played in.
+struct Recording {
+    players: Vec<String>,
+    initial_deck: Vec<Card>,
+    variant: Variant,
+    target: u8,
+    deals: Vec<Vec<u8>>,
+    plays: Vec<Intent>,
+}
+
+impl Replay {
+    pub fn new(recorded: &Game) -> Replay {
+        let players: Vec<String> = recorded.players.iter().map(|p| p.name.clone()).collect();
+        let plays = recorded.log.iter().filter_map(|entry| match entry {
+            LogEntry::Played(_, intent) => Some(intent.clone()),
+            _ => None
+        }).collect();
+        let recording = Recording {
+            players,
+            initial_deck: recorded.initial_deck.clone(),
+            variant: recorded.variant,
+            target: recorded.target,
+            deals: recorded.deals.clone(),
+            plays,
+        };
+        let game = recording.deal();
+        Replay {
+            recording,
+            position: 0,
+            game,
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The recorded plays, in order.
+    pub fn plays(&self) -> &[Intent] {
+        &self.recording.plays
+    }
+
+    pub fn len(&self) -> usize {
+        self.recording.plays.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recording.plays.is_empty()
+    }
+
+    /// Steps to the next play by making it in the game as it stands, and tells whether there was
+    /// one. A play the game no longer allows is not made, and the replay stays where it is.
+    pub fn forward(&mut self) -> Result<bool, String> {
+        let intent = match self.recording.plays.get(self.position) {
+            Some(intent) => intent.clone(),
+            None => return Ok(false)
+        };
+        self.game.play(intent.clone()).map_err(|e| diverged(&intent, e))?;
+        self.position += 1;
+        Ok(true)
+    }
+
+    pub fn backward(&mut self) -> Result<bool, String> {
+        if self.position > 0 {
+            self.seek(self.position - 1)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Rebuilds the game as it was after the first `position` plays. The game is left as it was
+    /// when one of those plays is no longer allowed.
+    pub fn seek(&mut self, position: usize) -> Result<(), String> {
+        let position = position.min(self.recording.plays.len());
+        let old = std::mem::replace(&mut self.game, self.recording.rebuild(position)?);
+        old.discard();
+        self.position = position;
+        Ok(())
+    }
+}
+
+/// Tells that `intent` could not be replayed.
+fn diverged(intent: &Intent, error: IntentError) -> String {
+    format!("replay diverged at '{}': {:?}", intent, error)
+}
+
+impl Recording {
+    /// The game as it was dealt.
+    fn deal(&self) -> Game {
+        let mut game = Game {
+            players: self.players.iter().enumerate().map(|(i, name)| Player::new(i as u8, name)).collect(),
+            variant: self.variant,
+            target: self.target,
+            deals: self.deals.clone(),
+            ..Game::with_deck(Deck::new(self.initial_deck.clone()))
+        };
+        game.start();
+        game
+    }
+
+    /// The game as it was after the first `position` plays.
+    fn rebuild(&self, position: usize) -> Result<Game, String> {
+        let mut game = self.deal();
+        for intent in &self.plays[..position] {
+            if let Err(e) = game.play(intent.clone()) {
+                game.discard();
+                return Err(diverged(intent, e));
+            }
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_steps_back_to_same_state() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.start();
+        let hand = game.players[0].hand.cards();
+        game.play(Intent::Trail(hand[0].clone())).unwrap();
+
+        let mut replay = Replay::new(&game);
+        assert_eq!(replay.len(), 1);
+        assert_eq!(replay.game.players[0].hand.cards(), hand);
+        assert_eq!(replay.forward(), Ok(true));
+        assert_eq!(replay.game.players[0].hand.len(), 3);
+        assert_eq!(replay.game.table.len(), 5);
+        assert_eq!(replay.forward(), Ok(false));
+        assert_eq!(replay.backward(), Ok(true));
+        assert_eq!(replay.game.players[0].hand.cards(), hand);
+    }
+
+    #[test]
+    fn test_replay_keeps_the_rules() {
+        // games are played out, taking all they can, until one has a sweep for the rules to ignore
+        let game = loop {
+            let mut game = Game {
+                players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+                variant: Variant::NoSweeps,
+                ..Default::default()
+            };
+            game.start();
+            while game.player_turn.is_some() {
+                let intent = game.legal_intents().into_iter().max_by_key(|intent| match intent {
+                    Intent::Take(_, piles) => piles.len() + 1,
+                    _ => 0,
+                }).unwrap();
+                game.play(intent).unwrap();
+            }
+            if game.players.iter().any(|p| p.sweeps > 0) {
+                break game;
+            }
+            game.discard();
+        };
+
+        let mut replay = Replay::new(&game);
+        while replay.forward().unwrap() {}
+        let totals = |game: &Game| game.points().iter().map(|p| p.total()).collect::<Vec<_>>();
+        assert_eq!(totals(&replay.game), totals(&game));
+        replay.game.discard();
+        game.discard();
+    }
+}
@@ -0,0 +1,215 @@
+//! How a game is set up before it starts: who sits at the table and who plays them, the rules,
+//! the match and the seed. A setup also writes a game in progress to a save file and reads it
+//! back, as the setup and the deal so far:
+//!
+//! ```text
+//! kasino 1
+//! seat player1
+//! seat player2
+//! ai medium
+//! variant standard
+//! target 11
+//! seed 42
+//! deal 3 5
+//! deck H:10 C:2 ...
+//! play take H:10 0
+//! ```
+//!
+//! `ai` applies to the seat above it, `deal` gives the points of a finished deal of the match, and
+//! `deck` and `play` the order the current deal is dealt in and the plays made in it.
+
+use crate::ai;
+use crate::kasino::Variant;
+use crate::types::{Game, Player, Deck, Card, Intent, LogEntry};
+
+const SAVE_HEADER: &str = "kasino 1";
+
+pub const MIN_SEATS: usize = 2;
+pub const MAX_SEATS: usize = 4;
+/// The ways a seat can be played, from the setup screen: by a person at the window, or by the
+/// computer as `ai::from_spec` reads it.
+pub const CONTROLLERS: [&str; 6] = ["human", "easy", "medium", "hard", "mcts", "expert"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Seat {
+    pub name: String,
+    /// How the computer plays the seat, as to `ai::from_spec`, or `None` for a person.
+    pub ai: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setup {
+    pub seats: Vec<Seat>,
+    pub variant: Variant,
+    /// The points that win the match, or 0 for a single deal.
+    pub target: u8,
+    pub seed: Option<u64>,
+}
+
+impl Seat {
+    pub fn new(number: usize) -> Seat {
+        Seat {
+            name: format!("player{}", number + 1),
+            ai: None,
+        }
+    }
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Setup {
+            seats: (0..MIN_SEATS).map(Seat::new).collect(),
+            variant: Variant::Standard,
+            target: 0,
+            seed: None,
+        }
+    }
+}
+
+impl Setup {
+    /// Adds seats at the end, or takes them away, to leave `count`.
+    pub fn set_seats(&mut self, count: usize) {
+        let count = count.clamp(MIN_SEATS, MAX_SEATS);
+        self.seats.truncate(count);
+        while self.seats.len() < count {
+            self.seats.push(Seat::new(self.seats.len()));
+        }
+    }
+
+    /// A game set up as told, not started yet. Seats without a name are named by their number.
+    pub fn game(&self) -> Result<Game, String> {
+        let players = self.seats.iter().enumerate().map(|(i, seat)| {
+            let name = if seat.name.trim().is_empty() { Seat::new(i).name } else { seat.name.trim().to_string() };
+            let mut player = Player::new(i as u8, &name);
+            if let Some(spec) = &seat.ai {
+                player.controller = Some(ai::from_spec(spec)?);
+            }
+            Ok(player)
+        }).collect::<Result<Vec<Player>, String>>()?;
+        let mut game = Game {
+            players,
+            variant: self.variant,
+            target: self.target,
+            seed: self.seed,
+            ..Default::default()
+        };
+        Deck::release(game.deck);
+        game.deck = game.shuffled_deck();
+        Ok(game)
+    }
+
+    /// The save file of `game`, played as set up here.
+    pub fn save(&self, game: &Game) -> String {
+        let cards = |cards: &[Card]| cards.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ");
+        let mut lines = vec!(SAVE_HEADER.to_string());
+        for seat in &self.seats {
+            lines.push(format!("seat {}", seat.name));
+            lines.extend(seat.ai.as_ref().map(|spec| format!("ai {}", spec)));
+        }
+        lines.push(format!("variant {}", self.variant));
+        lines.push(format!("target {}", self.target));
+        lines.extend(self.seed.map(|seed| format!("seed {}", seed)));
+        for deal in &game.deals {
+            lines.push(format!("deal {}", deal.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" ")));
+        }
+        lines.push(format!("deck {}", cards(&game.initial_deck)));
+        for entry in &game.log {
+            if let LogEntry::Played(_, intent) = entry {
+                lines.push(format!("play {}", intent));
+            }
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    /// Reads a save file back into its setup and the game, started and played up to where it was
+    /// saved.
+    pub fn load(text: &str) -> Result<(Setup, Game), String> {
+        let mut lines = text.lines();
+        if lines.next() != Some(SAVE_HEADER) {
+            return Err("not a saved game".to_string());
+        }
+        let mut setup = Setup {
+            seats: vec!(),
+            ..Default::default()
+        };
+        let mut deals = vec!();
+        let mut deck = None;
+        let mut plays = vec!();
+        for line in lines {
+            let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let number = |n: &str| n.parse().map_err(|_| format!("invalid number '{}'", n));
+            match word {
+                "seat" => setup.seats.push(Seat {
+                    name: rest.to_string(),
+                    ai: None,
+                }),
+                "ai" => setup.seats.last_mut().ok_or("ai before any seat")?.ai = Some(rest.to_string()),
+                "variant" => setup.variant = rest.parse()?,
+                "target" => setup.target = number(rest)? as u8,
+                "seed" => setup.seed = Some(number(rest)?),
+                "deal" => deals.push(rest.split_whitespace().map(|n| number(n).map(|n| n as u8)).collect::<Result<Vec<u8>, String>>()?),
+                "deck" => deck = Some(rest.split_whitespace().map(|c| c.parse()).collect::<Result<Vec<Card>, String>>()?),
+                "play" => plays.push(rest.parse::<Intent>()?),
+                "" => {},
+                _ => return Err(format!("unknown line '{}'", line)),
+            }
+        }
+        if setup.seats.len() < MIN_SEATS || setup.seats.len() > MAX_SEATS {
+            return Err(format!("a game has {} to {} seats", MIN_SEATS, MAX_SEATS));
+        }
+        if deals.iter().any(|deal| deal.len() != setup.seats.len()) {
+            return Err("a deal has points for every seat".to_string());
+        }
+
+        let mut game = setup.game()?;
+        game.deals = deals;
+        if let Some(cards) = deck {
+            Deck::release(game.deck);
+            game.deck = Deck::new(cards);
+        }
+        game.start();
+        for intent in plays {
+            let play = intent.to_string();
+            game.play(intent).map_err(|e| format!("cannot {}: {:?}", play, e))?;
+        }
+        Ok((setup, game))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let mut setup = Setup {
+            variant: Variant::NoSweeps,
+            target: 11,
+            seed: Some(42),
+            ..Default::default()
+        };
+        setup.set_seats(3);
+        setup.seats[1].ai = Some("easy".to_string());
+        let mut game = setup.game().unwrap();
+        assert_eq!(game.players[2].name, "player3");
+        game.start();
+        for _ in 0..5 {
+            let intent = game.legal_intents()[0].clone();
+            game.play(intent).unwrap();
+        }
+
+        let (loaded_setup, loaded) = Setup::load(&setup.save(&game)).unwrap();
+        assert_eq!(loaded_setup, setup);
+        assert_eq!(loaded.variant, Variant::NoSweeps);
+        assert_eq!(loaded.player_turn, game.player_turn);
+        assert_eq!(loaded.table.piles().iter().map(|p| p.cards()).collect::<Vec<_>>(),
+                   game.table.piles().iter().map(|p| p.cards()).collect::<Vec<_>>());
+        assert!(Setup::load("seat player1").is_err());
+
+        // the same seed deals the same match
+        assert_eq!(setup.game().unwrap().deck.cards(), Setup { seed: Some(42), ..Default::default() }.game().unwrap().deck.cards());
+        game.discard();
+        loaded.discard();
+    }
+}
@@ -0,0 +1,128 @@
+//! The summary shown when a deal is over: what every player scored in each category and for which
+//! cards, as the scoring breakdown has it, the points of the deal and of the match so far, and a
+//! button to go on.
+
+use crate::kasino::{Category, Points};
+use crate::types::Game;
+
+const WIDTH: f64 = 680.0;
+/// The most a row is high, when the window has room.
+const ROW_HEIGHT: f64 = 22.0;
+const BUTTON: (f64, f64) = (220.0, 34.0);
+
+/// Where the summary is drawn in the window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub panel: [f64; 4],
+    pub button: [f64; 4],
+    pub row_height: f64,
+}
+
+impl Game {
+    /// The rows of the summary, once the deal is over: for every player their name, a row for
+    /// each category with the cards or count it is scored for and its points, the points of the
+    /// deal and in a match the points so far; and last how the game stands.
+    pub fn summary(&self) -> Vec<[String; 3]> {
+        if self.player_turn.is_some() || self.round == 0 {
+            return vec!();
+        }
+        let standings = self.standings();
+        let mut rows = vec!();
+        for (i, breakdown) in self.breakdown().iter().enumerate() {
+            rows.push([self.players[i].name.clone(), String::new(), String::new()]);
+            for scored in breakdown {
+                let cards = scored.cards.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ");
+                let detail = match scored.category {
+                    Category::Cards => format!("{} cards", scored.count),
+                    Category::Spades => format!("{} spades {}", scored.count, cards),
+                    Category::Sweeps => format!("{} sweeps", scored.count),
+                    _ if scored.cards.is_empty() => "-".to_string(),
+                    _ => cards,
+                };
+                rows.push([format!("  {}", scored.category), detail, scored.points.to_string()]);
+            }
+            rows.push(["  this deal".to_string(), String::new(), Points::from_breakdown(breakdown).total().to_string()]);
+            if self.target > 0 {
+                rows.push(["  the match".to_string(), format!("playing to {}", self.target), standings[i].to_string()]);
+            }
+        }
+        rows.push([match self.match_winner() {
+            Some(winner) => format!("{} wins", self.players[winner as usize].name),
+            None if self.deal_pending() => "nobody has won yet".to_string(),
+            None => "nobody wins".to_string(),
+        }, String::new(), String::new()]);
+        rows
+    }
+
+    /// What the button under the summary does: deals the next round of a match, or leaves the
+    /// game that is over for the menu.
+    pub fn summary_button_label(&self) -> &'static str {
+        if self.deal_pending() { "deal the next round" } else { "back to the menu" }
+    }
+
+    /// Where the summary is drawn in a window of `width` and `height`, if it is shown. Rows are
+    /// made lower to fit the window.
+    pub fn summary_frame(&self, width: f64, height: f64) -> Option<Frame> {
+        let rows = self.summary().len();
+        if rows == 0 {
+            return None;
+        }
+        let margin = 20.0;
+        let row_height = ((height - 3.0 * margin - BUTTON.1) / (rows + 1) as f64).clamp(8.0, ROW_HEIGHT);
+        let panel_height = row_height * (rows + 1) as f64 + BUTTON.1 + 2.0 * margin;
+        let panel = [((width - WIDTH) / 2.0).max(0.0), ((height - panel_height) / 2.0).max(0.0), WIDTH.min(width), panel_height];
+        let button = [panel[0] + panel[2] - BUTTON.0 - margin, panel[1] + panel_height - BUTTON.1 - margin, BUTTON.0, BUTTON.1];
+        Some(Frame { panel, button, row_height })
+    }
+
+    /// Whether `point` is on the button under the summary in a window of `width` and `height`.
+    pub fn on_summary_button(&self, width: f64, height: f64, point: [f64; 2]) -> bool {
+        self.summary_frame(width, height).is_some_and(|frame| {
+            let [x, y, w, h] = frame.button;
+            point[0] >= x && point[0] <= x + w && point[1] >= y && point[1] <= y + h
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{Game, Player, Card};
+
+    #[test]
+    fn test_summary_follows_the_breakdown() {
+        let card = |s: &str| s.parse::<Card>().unwrap();
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            target: 11,
+            deals: vec!(vec!(5, 3)),
+            ..Default::default()
+        };
+        assert!(game.summary().is_empty());
+        game.round = 1;
+        game.players[0].score.append(&mut vec!(card("S:2"), card("S:5"), card("H:1")));
+        game.players[1].score.append(&mut vec!(card("D:10"), card("C:4")));
+        game.players[1].sweeps = 2;
+
+        let summary = game.summary();
+        // the row labelled `label` under the name of `player`
+        let row = |player: &str, label: &str| {
+            let from = summary.iter().position(|r| r[0] == player).unwrap();
+            summary[from..].iter().find(|r| r[0].trim() == label).unwrap().clone()
+        };
+        assert_eq!(row("player1", "cards"), ["  cards".to_string(), "3 cards".to_string(), "1".to_string()]);
+        assert_eq!(row("player1", "spades"), ["  spades".to_string(), "2 spades S:2 S:5".to_string(), "2".to_string()]);
+        assert_eq!(row("player1", "little casino")[1], "S:2");
+        assert_eq!(row("player1", "this deal"), ["  this deal".to_string(), String::new(), "5".to_string()]);
+        assert_eq!(row("player1", "the match")[2], "10");
+        assert_eq!(row("player2", "this deal")[2], "4");
+        assert_eq!(row("player2", "the match")[2], "7");
+        assert_eq!(summary.last().unwrap(), &["nobody has won yet".to_string(), String::new(), String::new()]);
+        assert_eq!(game.summary_button_label(), "deal the next round");
+
+        let frame = game.summary_frame(1024.0, 768.0).unwrap();
+        let [x, y, w, h] = frame.button;
+        assert!(game.on_summary_button(1024.0, 768.0, [x + w / 2.0, y + h / 2.0]));
+        assert!(y + h <= 768.0);
+        game.discard();
+    }
+}
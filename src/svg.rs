@@ -0,0 +1,59 @@
+//! Card art drawn from SVG. The drawing is read once and rasterized at the size the cards are
+//! shown at, so they stay sharp however the window scales them.
+
+use resvg::{tiny_skia, usvg};
+use std::path::Path;
+
+pub struct Drawing {
+    tree: usvg::Tree,
+}
+
+impl Drawing {
+    pub fn load(path: &Path) -> Result<Drawing, String> {
+        let data = std::fs::read(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        Drawing::parse(&data)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Drawing, String> {
+        let tree = usvg::Tree::from_data(data, &usvg::Options::default()).map_err(|e| e.to_string())?;
+        Ok(Drawing { tree })
+    }
+
+    /// The size of the drawing in its own units.
+    pub fn size(&self) -> (f64, f64) {
+        let size = self.tree.size();
+        (size.width() as f64, size.height() as f64)
+    }
+
+    /// The drawing `scale` times its size, as RGBA pixels row by row, and their width and height.
+    pub fn rasterize(&self, scale: f64) -> (Vec<u8>, u32, u32) {
+        let (width, height) = self.size();
+        let (width, height) = (((width * scale).ceil() as u32).max(1), ((height * scale).ceil() as u32).max(1));
+        let mut pixmap = tiny_skia::Pixmap::new(width, height).unwrap();
+        resvg::render(&self.tree, tiny_skia::Transform::from_scale(scale as f32, scale as f32), &mut pixmap.as_mut());
+        // textures are blended with straight alpha
+        let pixels = pixmap.pixels().iter().flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        }).collect();
+        (pixels, width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rasterize_at_scale() {
+        let drawing = Drawing::parse(br##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20">
+            <rect x="0" y="0" width="20" height="20" fill="#ff0000"/>
+        </svg>"##).unwrap();
+        assert_eq!(drawing.size(), (40.0, 20.0));
+        let (pixels, width, height) = drawing.rasterize(0.5);
+        assert_eq!((width, height), (20, 10));
+        let pixel = |x: usize, y: usize| &pixels[4 * (y * width as usize + x)..4 * (y * width as usize + x + 1)];
+        assert_eq!(pixel(2, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(15, 5)[3], 0);
+    }
+}
@@ -0,0 +1,101 @@
+//! Shows a game as text, for the terminal front end. Cards are written in the notation of the
+//! protocols, e.g. `H:10`, with hearts and diamonds in red when colors are on.
+
+use crate::types::{Card, Suit, Intent};
+use crate::view::PlayerView;
+use crate::kasino::Points;
+
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+pub fn card(card: &Card, color: bool) -> String {
+    match card.suit {
+        Suit::HEARTS | Suit::DIAMONDS if color && !card.is_hidden() => format!("{}{}{}", RED, card, RESET),
+        _ => card.to_string(),
+    }
+}
+
+fn cards(cards: &[Card], color: bool) -> String {
+    cards.iter().map(|c| card(c, color)).collect::<Vec<_>>().join(" ")
+}
+
+/// The table as the seat of `view` sees it: the other players, the piles numbered as in moves,
+/// and the seat's own hand.
+pub fn render(view: &PlayerView, color: bool) -> Vec<String> {
+    let mut lines = vec!(format!("round {}{}, {} cards left in the deck",
+                                 view.round, if view.last_round { " (last)" } else { "" }, view.deck));
+    for (i, p) in view.players.iter().enumerate() {
+        let turn = if view.turn == Some(i as u8) { "*" } else { " " };
+        let hand = match &p.shown {
+            Some(shown) => cards(shown, color),
+            None if view.seat == Some(i as u8) => cards(&view.hand, color),
+            None => format!("{} cards", p.hand),
+        };
+        lines.push(format!("{} {:<12} hand: {}", turn, p.name, hand));
+        lines.push(format!("  {:<12} captured {} cards, {} sweeps", "", p.captured.len(), p.sweeps));
+    }
+    lines.push("table:".to_string());
+    for (i, pile) in view.table.iter().enumerate() {
+        let build = match pile.owner {
+            Some(owner) => format!(", build of {}", view.players[owner as usize].name),
+            None => String::new(),
+        };
+        lines.push(format!("  {}: {} (counts {}{})", i, cards(&pile.cards, color), pile.value, build));
+    }
+    lines
+}
+
+/// The legal moves, numbered from 1 as they are chosen.
+pub fn choices(intents: &[Intent]) -> Vec<String> {
+    intents.iter().enumerate().map(|(i, intent)| format!("  {:>2}) {}", i + 1, intent)).collect()
+}
+
+/// The move typed in `input`, either the number of a choice or a move in notation.
+pub fn choose(input: &str, intents: &[Intent]) -> Result<Intent, String> {
+    let input = input.trim();
+    match input.parse::<usize>() {
+        Ok(number) => number.checked_sub(1).and_then(|i| intents.get(i)).cloned()
+            .ok_or(format!("there is no move {}", number)),
+        Err(_) => input.parse(),
+    }
+}
+
+pub fn score(names: &[String], points: &[Points]) -> Vec<String> {
+    let mut lines = vec!(format!("{:<12}{:>7}{:>8}{:>8}{:>8}{:>6}{:>8}{:>7}",
+                                 "", "cards", "spades", "big", "little", "aces", "sweeps", "total"));
+    for (name, p) in names.iter().zip(points) {
+        lines.push(format!("{:<12}{:>7}{:>8}{:>8}{:>8}{:>6}{:>8}{:>7}", name, p.cards, p.spades,
+                           p.big_casino, p.little_casino, p.aces, p.sweeps, p.total()));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Game, Player};
+
+    #[test]
+    fn test_render_and_choose() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.start();
+        let intents = game.legal_intents();
+        let lines = render(&game.view(1), false);
+        assert!(lines.contains(&"* player1      hand: 4 cards".to_string()));
+        assert!(lines.iter().any(|l| l.starts_with("  player2      hand: ")));
+        assert_eq!(lines.iter().filter(|l| l.contains("(counts")).count(), 4);
+
+        assert_eq!(choose("1", &intents), Ok(intents[0].clone()));
+        assert_eq!(choose(&format!(" {} ", intents[2]), &intents), Ok(intents[2].clone()));
+        assert!(choose("0", &intents).is_err());
+        assert!(choose("fold", &intents).is_err());
+
+        let hearts = Card::new(Suit::HEARTS, 10, crate::types::Deck::empty());
+        assert_eq!(card(&hearts, true), "\x1b[31mH:10\x1b[0m");
+        assert_eq!(card(&hearts, false), "H:10");
+        game.discard();
+    }
+}
@@ -1,30 +1,31 @@
-use std::prelude::v1::{Vec, IntoIterator, Iterator};
+use std::prelude::v1::{Vec, IntoIterator};
 use std::fmt;
-use std::fmt::{Formatter, Error};
+use std::fmt::Formatter;
 use std::default::Default;
-use gfx_device_gl::Texture;
-use std::rc::Rc;
-use crate::graphic::{GraphicsEnv, SpriteRef};
-use piston_window::texture::ImageSize;
-use piston_window::G2dTexture;
+use crate::graphic::SpriteRef;
+use crate::history::Snapshot;
+use crate::kasino::Variant;
+use crate::events::Observer;
+use crate::ai::Controller;
 use piston::Key;
-use std::ops::{DerefMut, Deref};
 use core::slice;
 use uuid::{Uuid, UuidVersion};
 use std::collections::HashMap;
-use std::sync::{RwLock, Arc, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{RwLock, RwLockWriteGuard};
 use std::option::Option;
-use sprite::Sprite;
+use std::str::FromStr;
 
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct DeckRef(Uuid);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Table(Vec<DeckRef>);
 
 #[derive(Debug)]
 pub struct Deck {
+    // kept for debugging, decks are looked up by their ref
+    #[allow(dead_code)]
     id: DeckRef,
     cards: Vec<Card>,
 }
@@ -33,10 +34,36 @@ pub struct Game {
     pub players: Vec<Player>,
     pub deck: DeckRef,
     pub table: Table,
+    pub builds: Vec<Build>,
     pub last_round: bool,
-    pub graphics_env: Option<GraphicsEnv>,
+    pub last_capture: Option<u8>,
+    pub round: u8,
     pub player_turn: Option<u8>,
-    pub interactions: Vec<PlayerInteraction>,
+    pub initial_deck: Vec<Card>,
+    pub log: Vec<LogEntry>,
+    pub undo_enabled: bool,
+    pub history: Vec<Snapshot>,
+    pub redo: Vec<Intent>,
+    pub observers: Vec<Box<dyn Observer>>,
+    /// The rules the game is played by.
+    pub variant: Variant,
+    /// The points that win a match of several deals, or 0 to play a single deal.
+    pub target: u8,
+    /// The points of every player in each deal of the match played so far.
+    pub deals: Vec<Vec<u8>>,
+    /// Shuffles every deal of the match the same way, when given.
+    pub seed: Option<u64>,
+}
+
+/// Who a window shows the game to, and so which hands it shows face up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Viewer {
+    /// Whoever is in turn, as when the players share the window.
+    InTurn,
+    /// The seat the window is played from.
+    Seat(u8),
+    /// Someone without a seat, who is shown no hand.
+    Spectator,
 }
 
 #[derive(Debug)]
@@ -45,6 +72,8 @@ pub struct Player {
     pub name: String,
     pub hand: DeckRef,
     pub score: DeckRef,
+    pub sweeps: u8,
+    pub controller: Option<Box<dyn Controller>>,
 }
 
 #[derive(Debug, Clone)]
@@ -63,6 +92,14 @@ pub enum Suit {
     HEARTS
 }
 
+/// A pile on the table that a player has built towards a value they hold in hand.
+#[derive(Debug, Clone)]
+pub struct Build {
+    pub pile: DeckRef,
+    pub value: u8,
+    pub owner: u8,
+}
+
 lazy_static! {
     static ref DECKS: RwLock<HashMap<DeckRef, Deck>> = RwLock::new(HashMap::new());
 }
@@ -84,7 +121,7 @@ impl fmt::Display for Deck {
 
 impl fmt::Display for DeckRef {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        DECKS.read().unwrap().get(&self).unwrap().fmt(f)
+        DECKS.read().unwrap().get(self).unwrap().fmt(f)
     }
 }
 
@@ -101,14 +138,34 @@ impl fmt::Display for Game {
 
 impl Default for Game {
     fn default() -> Self {
+        Game::with_deck(Deck::build())
+    }
+}
+
+impl Game {
+    /// A game to be dealt from `deck`. Games built with `..Default::default()` register a shuffled
+    /// deck of their own, which is lost when the deck is replaced, so a game that brings its own
+    /// deck is built with `..Game::with_deck(deck)` instead.
+    pub fn with_deck(deck: DeckRef) -> Game {
         Game{
             players: vec!(),
-            deck: Deck::build(),
+            deck,
             table: Table(vec!()),
+            builds: vec!(),
             last_round: false,
-            graphics_env: None::<GraphicsEnv>,
+            last_capture: None,
+            round: 0,
             player_turn: None,
-            interactions: vec!(),
+            initial_deck: vec!(),
+            log: vec!(),
+            undo_enabled: true,
+            history: vec!(),
+            redo: vec!(),
+            observers: vec!(),
+            variant: Variant::Standard,
+            target: 0,
+            deals: vec!(),
+            seed: None,
         }
     }
 }
@@ -121,42 +178,85 @@ impl fmt::Display for Card {
             Suit::DIAMONDS => "D",
             Suit::HEARTS => "H",
         };
+        if self.is_hidden() {
+            return write!(f, "??");
+        }
         write!(f, "{}:{}", token, self.value)
     }
 }
 
+/// Cards are written as their suit and value, as in `S:2` for the two of spades, and hidden cards
+/// as `??`.
+impl FromStr for Card {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "??" {
+            return Ok(Card::hidden());
+        }
+        let mut parts = s.splitn(2, ':');
+        let suit = match parts.next() {
+            Some("C") => Suit::CLUBS,
+            Some("S") => Suit::SPADES,
+            Some("D") => Suit::DIAMONDS,
+            Some("H") => Suit::HEARTS,
+            _ => return Err(format!("invalid card '{}'", s)),
+        };
+        match parts.next().and_then(|v| v.parse::<u8>().ok()) {
+            Some(value) if (1..14).contains(&value) => Ok(Card::new(suit, value, DeckRef::none())),
+            _ => Err(format!("invalid card '{}'", s)),
+        }
+    }
+}
+
 impl DeckRef {
+    /// Refers to no deck, for cards that are only described, such as parsed ones.
+    pub fn none() -> DeckRef {
+        DeckRef(Uuid::nil())
+    }
+
     pub fn is_empty(&self) -> bool {
-        DECKS.read().unwrap().get(&self).unwrap().is_empty()
+        DECKS.read().unwrap().get(self).unwrap().is_empty()
     }
 
     pub fn has_cards(&self, count: usize) -> bool {
-        DECKS.read().unwrap().get(&self).unwrap().has_cards(count)
+        DECKS.read().unwrap().get(self).unwrap().has_cards(count)
     }
 
-    fn append(&mut self, cards: &mut Vec<Card>) {
-        DECKS.write().unwrap().get_mut(&self).unwrap().append(cards)
+    pub fn append(&mut self, cards: &mut Vec<Card>) {
+        DECKS.write().unwrap().get_mut(self).unwrap().append(cards)
     }
 
     pub fn draw(&mut self, count: usize) -> Option<Vec<Card>> {
-        DECKS.write().unwrap().get_mut(&self).unwrap().draw(count)
+        DECKS.write().unwrap().get_mut(self).unwrap().draw(count)
     }
 
-    fn len(&self) -> usize {
-        DECKS.read().unwrap().get(&self).unwrap().len()
+    pub fn len(&self) -> usize {
+        DECKS.read().unwrap().get(self).unwrap().len()
     }
 
-    fn transfer(&mut self, card: &Card, to: &mut Self) {
+    /// Moves the card equal to `card` from this deck to the end of `to`, keeping its sprite.
+    pub fn transfer(&mut self, card: &Card, to: &mut Self) -> bool {
         let mut guard = DECKS.write().unwrap();
-        guard.get_mut(&self).unwrap().remove(card);
-        let card = card.to_owned();
-        //card.deck = to.to_owned();
-        guard.get_mut(&to).unwrap().append(&mut vec!(card));
+        match guard.get_mut(self).unwrap().remove(card) {
+            Some(card) => {
+                guard.get_mut(to).unwrap().append(&mut vec!(card));
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Moves all cards of this deck to the end of `to`.
+    pub fn transfer_all(&mut self, to: &mut Self) {
+        let mut guard = DECKS.write().unwrap();
+        let mut cards = guard.get_mut(self).unwrap().draw_all();
+        guard.get_mut(to).unwrap().append(&mut cards);
     }
 
-    fn remove(&mut self, card: &Card) {
+    pub fn remove(&mut self, card: &Card) -> Option<Card> {
         let mut guard = DECKS.write().unwrap();
-        guard.get_mut(&self).unwrap().remove(&card);
+        guard.get_mut(self).unwrap().remove(card)
     }
 
     pub fn iter(&mut self) -> DeckRefIter {
@@ -167,7 +267,18 @@ impl DeckRef {
     }
 
     pub fn contains(&self, card: &Card) -> bool {
-        DECKS.read().unwrap().get(&self).unwrap().contains(card)
+        DECKS.read().unwrap().get(self).unwrap().contains(card)
+    }
+
+    /// Replaces the cards of the deck, returning the previous ones.
+    pub fn set_cards(&mut self, cards: Vec<Card>) -> Vec<Card> {
+        let mut guard = DECKS.write().unwrap();
+        std::mem::replace(&mut guard.get_mut(self).unwrap().cards, cards)
+    }
+
+    /// A snapshot of the cards currently in the deck.
+    pub fn cards(&self) -> Vec<Card> {
+        DECKS.read().unwrap().get(self).unwrap().cards.clone()
     }
 }
 
@@ -178,6 +289,8 @@ impl Player {
             name: name.to_string(),
             hand: Deck::empty(),
             score: Deck::empty(),
+            sweeps: 0,
+            controller: None,
         }
     }
 }
@@ -196,33 +309,42 @@ impl Deck {
         deck_ref
     }
 
+    /// Unregisters a deck that is no longer in use.
+    pub fn release(deck: DeckRef) {
+        DECKS.write().unwrap().remove(&deck);
+    }
+
+    /// Whether `deck` is still registered, that is, not released yet.
+    pub fn registered(deck: DeckRef) -> bool {
+        DECKS.read().unwrap().contains_key(&deck)
+    }
+
     pub fn singleton(card: Card) -> DeckRef {
         Self::new(vec![card])
     }
 
     fn build() -> DeckRef {
-        let mut cards = Vec::new();
-        let mut deck = Deck::empty();
-        for value in 1..14 {
-            cards.push(Card::new(Suit::CLUBS, value, deck));
-        }
-        for value in 1..14 {
-            cards.push(Card::new(Suit::SPADES, value, deck));
-        }
-        for value in 1..14 {
-            cards.push(Card::new(Suit::DIAMONDS, value, deck));
-        }
-        for value in 1..14 {
-            cards.push(Card::new(Suit::HEARTS, value, deck));
-        }
+        Self::shuffled(&mut rand::thread_rng())
+    }
 
+    /// A full deck of 52 cards, shuffled with `rng`. A seeded `rng` deals the same game every time.
+    pub fn shuffled<R: rand::Rng>(rng: &mut R) -> DeckRef {
         use rand::seq::SliceRandom;
-        use rand::thread_rng;
 
-        let mut rng = thread_rng();
-        cards.shuffle(&mut rng);
-        deck.append(&mut cards);
-        deck
+        let mut cards = Self::standard();
+        cards.shuffle(rng);
+        Deck::new(cards)
+    }
+
+    /// The 52 cards of a full deck, by suit and value.
+    pub fn standard() -> Vec<Card> {
+        let mut cards = Vec::new();
+        for suit in &[Suit::CLUBS, Suit::SPADES, Suit::DIAMONDS, Suit::HEARTS] {
+            for value in 1..14 {
+                cards.push(Card::new(suit.clone(), value, DeckRef::none()));
+            }
+        }
+        cards
     }
 
     pub fn draw(&mut self, count: usize) -> Option<Vec<Card>> {
@@ -238,6 +360,10 @@ impl Deck {
         }
     }
 
+    fn draw_all(&mut self) -> Vec<Card> {
+        self.cards.drain(..).collect()
+    }
+
     pub fn is_empty(&self) -> bool {
         !self.has_cards(1)
     }
@@ -263,8 +389,9 @@ impl Deck {
         self.cards.len()
     }
 
-    fn remove(&mut self, card: &Card) {
-        self.remove(card);
+    fn remove(&mut self, card: &Card) -> Option<Card> {
+        let pos = self.cards.iter().position(|c| c == card)?;
+        Some(self.cards.remove(pos))
     }
 }
 
@@ -297,8 +424,7 @@ impl<'a> IntoIterator for &'a Deck {
     type IntoIter = slice::Iter<'a, Card>;
 
     fn into_iter(self) -> slice::Iter<'a, Card> {
-        let d: &'a Vec<Card> = &self.cards;
-        d.into_iter()
+        self.cards.iter()
     }
 }
 
@@ -316,8 +442,14 @@ impl<'a> IntoIterator for &'a DeckRef {
 */
 
 impl Table {
-    pub fn new_pile(&mut self, cards: Vec<Card>) {
-        self.0.push(Deck::new(cards));
+    pub fn new(piles: Vec<DeckRef>) -> Table {
+        Table(piles)
+    }
+
+    pub fn new_pile(&mut self, cards: Vec<Card>) -> DeckRef {
+        let pile = Deck::new(cards);
+        self.0.push(pile);
+        pile
     }
 
     pub fn contains(&self, card: &Card) -> bool {
@@ -328,6 +460,31 @@ impl Table {
         }
         false
     }
+
+    /// The index of the pile holding `card`, if the card is on the table.
+    pub fn pile_of(&self, card: &Card) -> Option<usize> {
+        self.0.iter().position(|pile| pile.contains(card))
+    }
+
+    pub fn get(&self, pile: usize) -> Option<DeckRef> {
+        self.0.get(pile).copied()
+    }
+
+    pub fn remove(&mut self, pile: usize) -> DeckRef {
+        self.0.remove(pile)
+    }
+
+    pub fn piles(&self) -> &[DeckRef] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 impl Player {
@@ -345,6 +502,15 @@ impl Card {
             sprite: None
         }
     }
+
+    /// A card whose face is not known, such as one in another player's hand.
+    pub fn hidden() -> Card {
+        Card::new(Suit::CLUBS, 0, DeckRef::none())
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.value == 0
+    }
 }
 
 impl PartialEq for Card {
@@ -353,18 +519,78 @@ impl PartialEq for Card {
     }
 }
 
+#[derive(PartialEq)]
 pub enum PlayerInteraction {
     Click(SpriteRef),
     Keyboard(Key)
 }
 
-pub struct Intent {
+/// A complete move by the player in turn. Table piles are referred to by their index on the
+/// table, so an intent can be re-applied to a replayed game.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Intent {
+    Trail(Card),
+    Take(Card, Vec<usize>),
+    Build(Card, Vec<usize>, u8),
+}
+
+/// Everything that changed the cards in play, in the order it happened.
+#[derive(Debug, Clone)]
+pub enum LogEntry {
+    Dealt(Option<u8>, Vec<Card>),
+    Played(u8, Intent),
+    Swept(u8),
+    Cleared(u8, Vec<Card>),
+}
 
+/// Intents are written as `trail C:5`, `take C:5 0 2` and `build 9 C:5 1`, with the piles
+/// given by their index on the table.
+impl fmt::Display for Intent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let piles = |piles: &Vec<usize>| piles.iter().map(|p| format!(" {}", p)).collect::<String>();
+        match self {
+            Intent::Trail(card) => write!(f, "trail {}", card),
+            Intent::Take(card, p) => write!(f, "take {}{}", card, piles(p)),
+            Intent::Build(card, p, value) => write!(f, "build {} {}{}", value, card, piles(p)),
+        }
+    }
 }
 
-pub enum IntentError {
-    InvalidIntent,
-    IllegalIntent(String)
+impl FromStr for Intent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let kind = words.next().ok_or("empty intent")?;
+        let value = if kind == "build" {
+            let value = words.next().ok_or("missing build value")?;
+            Some(value.parse::<u8>().map_err(|_| format!("invalid build value '{}'", value))?)
+        } else {
+            None
+        };
+        let card = words.next().ok_or("missing card")?.parse::<Card>()?;
+        let piles = words.map(|w| w.parse::<usize>().map_err(|_| format!("invalid pile '{}'", w)))
+            .collect::<Result<Vec<usize>, String>>()?;
+        match (kind, value) {
+            ("trail", _) if piles.is_empty() => Ok(Intent::Trail(card)),
+            ("take", _) => Ok(Intent::Take(card, piles)),
+            ("build", Some(value)) => Ok(Intent::Build(card, piles, value)),
+            _ => Err(format!("unknown intent '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cards = |cards: &Vec<Card>| cards.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", ");
+        match self {
+            LogEntry::Dealt(Some(player), cards_dealt) => write!(f, "dealt player {}: {}", player, cards(cards_dealt)),
+            LogEntry::Dealt(None, cards_dealt) => write!(f, "dealt table: {}", cards(cards_dealt)),
+            LogEntry::Played(player, intent) => write!(f, "player {}: {}", player, intent),
+            LogEntry::Swept(player) => write!(f, "player {}: sweep", player),
+            LogEntry::Cleared(player, cleared) => write!(f, "player {} takes the rest: {}", player, cards(cleared)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -377,21 +603,31 @@ mod tests {
     fn test_deck_build() {
         let mut d = Deck::build();
         let draw = d.draw(4).unwrap();
+        assert_eq!(draw.len(), 4);
+    }
+
+    #[test]
+    fn test_notation() {
+        let card: Card = "S:2".parse().unwrap();
+        assert_eq!(card.suit, Suit::SPADES);
+        assert_eq!(card.value, 2);
+        assert!("X:2".parse::<Card>().is_err());
+        assert!("H:14".parse::<Card>().is_err());
+        assert_eq!(Card::hidden().to_string().parse::<Card>().map(|c| c.is_hidden()), Ok(true));
+
+        for notation in &["trail C:5", "take H:13 0 2", "build 9 D:4 1"] {
+            let intent: Intent = notation.parse().unwrap();
+            assert_eq!(&intent.to_string(), notation);
+        }
+        assert!("take".parse::<Intent>().is_err());
+        assert!("trail C:5 1".parse::<Intent>().is_err());
     }
 
     #[test]
     fn test_game_creation() {
-        let mut p1 = Player{
-            id: 1,
-            name: "player1".to_string(),
-            hand: Deck(vec![])
-        };
-        let mut p2 = Player{
-            id: 2,
-            name: "player2".to_string(),
-            hand: Deck(vec![])
-        };
-        let mut deck = Deck::build();
+        let p1 = Player::new(1, "player1");
+        let p2 = Player::new(2, "player2");
+        let deck = Deck::build();
 
         let mut game = Game{
             players: vec![p1, p2],
@@ -404,7 +640,7 @@ mod tests {
         println!("Before setup:");
         println!();
         println!("{}", &game);
-        game.setup();
+        game.start();
         println!("After setup:");
         println!();
         println!("{}", &game);
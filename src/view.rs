@@ -0,0 +1,281 @@
+use crate::types::{Game, Player, Card, Deck, Table, Build};
+use crate::kasino::Variant;
+
+/// What one seat may know of a game: its own hand, the table, and everything that has been played
+/// in the open, but neither the other hands nor the order of the deck. Every part of the program
+/// that acts for a seat, or shows the game to one, goes through its view. Spectators have a view
+/// without a seat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerView {
+    pub seat: Option<u8>,
+    pub round: u8,
+    pub last_round: bool,
+    pub deck: usize,
+    pub turn: Option<u8>,
+    pub last_capture: Option<u8>,
+    pub hand: Vec<Card>,
+    pub players: Vec<SeatView>,
+    pub table: Vec<PileView>,
+    /// The rules, and the match the deal is part of, which the points depend on.
+    pub variant: Variant,
+    pub target: u8,
+    pub deals: Vec<Vec<u8>>,
+}
+
+/// A player as seen from another seat. Captured cards are known, as every one of them was taken
+/// in the open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeatView {
+    pub name: String,
+    pub hand: usize,
+    /// The cards in hand, when the hands are open to spectators.
+    pub shown: Option<Vec<Card>>,
+    pub captured: Vec<Card>,
+    pub sweeps: u8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PileView {
+    pub cards: Vec<Card>,
+    pub value: u8,
+    pub owner: Option<u8>,
+}
+
+impl Game {
+    pub fn view(&self, seat: u8) -> PlayerView {
+        self.project(Some(seat), false)
+    }
+
+    /// The view of a spectator, who sees every hand if `open_hands` is set, and none otherwise.
+    pub fn spectator_view(&self, open_hands: bool) -> PlayerView {
+        self.project(None, open_hands)
+    }
+
+    fn project(&self, seat: Option<u8>, open_hands: bool) -> PlayerView {
+        PlayerView {
+            seat,
+            round: self.round,
+            last_round: self.last_round,
+            deck: self.deck.len(),
+            turn: self.player_turn,
+            last_capture: self.last_capture,
+            hand: seat.map(|s| self.players[s as usize].hand.cards()).unwrap_or_default(),
+            players: self.players.iter().map(|p| SeatView {
+                name: p.name.clone(),
+                hand: p.hand.len(),
+                shown: if open_hands { Some(p.hand.cards()) } else { None },
+                captured: p.score.cards(),
+                sweeps: p.sweeps,
+            }).collect(),
+            table: self.table.piles().iter().enumerate().map(|(i, pile)| PileView {
+                cards: pile.cards(),
+                value: self.pile_value(i).unwrap(),
+                owner: self.builds.iter().find(|b| b.pile == *pile).map(|b| b.owner),
+            }).collect(),
+            variant: self.variant,
+            target: self.target,
+            deals: self.deals.clone(),
+        }
+    }
+}
+
+impl PlayerView {
+    /// The cards the seat has not seen, in the order of a full deck: the other hands and the deck.
+    pub fn unknown(&self) -> Vec<Card> {
+        let mut seen: Vec<&Card> = self.hand.iter().collect();
+        seen.extend(self.table.iter().flat_map(|p| p.cards.iter()));
+        seen.extend(self.players.iter().flat_map(|p| p.captured.iter()));
+        seen.extend(self.players.iter().flat_map(|p| p.shown.iter().flatten()));
+        Deck::standard().into_iter().filter(|c| !seen.contains(&c)).collect()
+    }
+
+    /// Makes `game` mirror the view. The cards the seat has not seen are hidden cards.
+    pub fn apply(&self, game: &mut Game) {
+        self.write(game, |count| (0..count).map(|_| Card::hidden()).collect());
+    }
+
+    /// A headless game that looks to the seat just like the one it was viewed from. The cards it
+    /// has not seen are dealt to the other hands and the deck in the order of a full deck, so the
+    /// game reveals nothing the view does not.
+    pub fn to_game(&self) -> Game {
+        let mut game = Game {
+            players: self.players.iter().enumerate().map(|(i, p)| Player::new(i as u8, &p.name)).collect(),
+            undo_enabled: false,
+            ..Game::with_deck(Deck::empty())
+        };
+        let mut unknown = self.unknown().into_iter();
+        self.write(&mut game, |count| unknown.by_ref().take(count).collect());
+        game
+    }
+
+    fn write<F: FnMut(usize) -> Vec<Card>>(&self, game: &mut Game, mut unseen: F) {
+        game.round = self.round;
+        game.last_round = self.last_round;
+        game.player_turn = self.turn;
+        game.last_capture = self.last_capture;
+        game.variant = self.variant;
+        game.target = self.target;
+        game.deals = self.deals.clone();
+        for (i, (player, seen)) in game.players.iter_mut().zip(&self.players).enumerate() {
+            if self.seat == Some(i as u8) {
+                player.hand.set_cards(self.hand.clone());
+            } else if let Some(shown) = &seen.shown {
+                player.hand.set_cards(shown.clone());
+            } else {
+                player.hand.set_cards(unseen(seen.hand));
+            }
+            player.name = seen.name.clone();
+            player.score.set_cards(seen.captured.clone());
+            player.sweeps = seen.sweeps;
+        }
+        game.deck.set_cards(unseen(self.deck));
+
+        for pile in game.table.piles() {
+            Deck::release(*pile);
+        }
+        let piles: Vec<_> = self.table.iter().map(|p| Deck::new(p.cards.clone())).collect();
+        game.builds = self.table.iter().zip(&piles).filter_map(|(p, pile)| p.owner.map(|owner| Build {
+            pile: *pile,
+            value: p.value,
+            owner,
+        })).collect();
+        game.table = Table::new(piles);
+    }
+
+    /// The view as the lines of the network and bot protocols.
+    pub fn to_lines(&self) -> Vec<String> {
+        let cards = |cards: &[Card]| cards.iter().map(|c| format!(" {}", c)).collect::<String>();
+        let seat = |seat: Option<u8>| seat.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+        let mut lines = vec!(
+            format!("seat {}", seat(self.seat)),
+            format!("round {} {}", self.round, if self.last_round { 1 } else { 0 }),
+            format!("deck {}", self.deck),
+            format!("turn {}", seat(self.turn)),
+            format!("capture {}", seat(self.last_capture)),
+            format!("hand{}", cards(&self.hand)),
+            format!("variant {}", self.variant),
+            format!("target {}", self.target),
+        );
+        for deal in &self.deals {
+            lines.push(format!("deal{}", deal.iter().map(|p| format!(" {}", p)).collect::<String>()));
+        }
+        for (i, p) in self.players.iter().enumerate() {
+            lines.push(format!("player {} {} {} {}", i, p.hand, p.sweeps, p.name));
+            lines.push(format!("captured {}{}", i, cards(&p.captured)));
+            if let Some(shown) = &p.shown {
+                lines.push(format!("shown {}{}", i, cards(shown)));
+            }
+        }
+        for (i, p) in self.table.iter().enumerate() {
+            lines.push(format!("pile {} {} {}{}", i, p.value, seat(p.owner), cards(&p.cards)));
+        }
+        lines
+    }
+
+    pub fn from_lines(lines: &[String]) -> Result<PlayerView, String> {
+        let mut view = PlayerView {
+            seat: None,
+            round: 0,
+            last_round: false,
+            deck: 0,
+            turn: None,
+            last_capture: None,
+            hand: vec!(),
+            players: vec!(),
+            table: vec!(),
+            variant: Variant::Standard,
+            target: 0,
+            deals: vec!(),
+        };
+        let cards = |words: &[&str]| words.iter().map(|w| w.parse()).collect::<Result<Vec<Card>, String>>();
+        for line in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let malformed = || format!("malformed line '{}'", line);
+            let number = |i: usize| words.get(i).and_then(|w| w.parse::<usize>().ok()).ok_or_else(malformed);
+            let seat = |i: usize| words.get(i).and_then(|w| w.parse::<u8>().ok());
+            match words.first() {
+                Some(&"seat") => view.seat = seat(1),
+                Some(&"round") => {
+                    view.round = number(1)? as u8;
+                    view.last_round = number(2)? == 1;
+                },
+                Some(&"deck") => view.deck = number(1)?,
+                Some(&"turn") => view.turn = seat(1),
+                Some(&"capture") => view.last_capture = seat(1),
+                Some(&"hand") => view.hand = cards(&words[1..])?,
+                Some(&"variant") => view.variant = words.get(1).ok_or_else(malformed)?.parse()?,
+                Some(&"target") => view.target = number(1)? as u8,
+                Some(&"deal") => view.deals.push((1..words.len()).map(|i| number(i).map(|n| n as u8)).collect::<Result<Vec<u8>, String>>()?),
+                Some(&"player") => {
+                    if number(1)? != view.players.len() {
+                        return Err(malformed());
+                    }
+                    view.players.push(SeatView {
+                        name: words.get(4..).ok_or_else(malformed)?.join(" "),
+                        hand: number(2)?,
+                        shown: None,
+                        captured: vec!(),
+                        sweeps: number(3)? as u8,
+                    });
+                },
+                Some(&"captured") => {
+                    let player = view.players.get_mut(number(1)?).ok_or_else(malformed)?;
+                    player.captured = cards(&words[2..])?;
+                },
+                Some(&"shown") => {
+                    let player = view.players.get_mut(number(1)?).ok_or_else(malformed)?;
+                    player.shown = Some(cards(&words[2..])?);
+                },
+                Some(&"pile") => {
+                    if number(1)? != view.table.len() || words.len() < 4 {
+                        return Err(malformed());
+                    }
+                    view.table.push(PileView {
+                        cards: cards(&words[4..])?,
+                        value: number(2)? as u8,
+                        owner: seat(3),
+                    });
+                },
+                _ => return Err(format!("unknown line '{}'", line)),
+            }
+        }
+        if let Some(seat) = view.seat.filter(|s| *s as usize >= view.players.len()) {
+            return Err(format!("there is no seat {}", seat));
+        }
+        Ok(view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Intent;
+
+    #[test]
+    fn test_view_hides_what_the_seat_cannot_see() {
+        let mut game = Game {
+            players: vec!(Player::new(0, "player1"), Player::new(1, "player2")),
+            ..Default::default()
+        };
+        game.start();
+        let card = game.players[0].hand.cards()[0].clone();
+        game.play(Intent::Trail(card)).unwrap();
+
+        game.variant = Variant::NoSweeps;
+        game.target = 11;
+        game.deals = vec!(vec!(4, 7));
+        let view = game.view(1);
+        assert_eq!(PlayerView::from_lines(&view.to_lines()), Ok(view.clone()));
+        assert_eq!(view.unknown().len(), 52 - 4 - 5);
+
+        // a game rebuilt from the view has the same view, but does not know the other hand
+        let rebuilt = view.to_game();
+        assert_eq!(rebuilt.view(1), view);
+        assert_eq!(rebuilt.legal_intents(), game.legal_intents());
+        assert_eq!((rebuilt.variant, rebuilt.target, rebuilt.deals.clone()), (Variant::NoSweeps, 11, vec!(vec!(4, 7))));
+        let mut rebuilt_hand = rebuilt.players[0].hand.cards();
+        rebuilt_hand.append(&mut rebuilt.deck.cards());
+        assert_eq!(rebuilt_hand, view.unknown());
+        rebuilt.discard();
+    }
+}